@@ -0,0 +1,52 @@
+//! Derive macros for the `arc-cell` crate, re-exported by it with the `derive` feature.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Implements `AtomicCellStorable` and `AtomicCellConstInit` for a fieldless enum.
+///
+/// Like `arc_cell::storable_enum!`, each variant is stored as its position in the enum, so
+/// explicit discriminants are allowed and cells created with `const_new` start out holding the
+/// first variant.
+#[proc_macro_derive(AtomicCellStorable)]
+pub fn derive_atomic_cell_storable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "AtomicCellStorable can only be derived for fieldless enums",
+            ))
+        }
+    };
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "enums without variants have no value to store",
+        ));
+    }
+    if let Some(variant) = data
+        .variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return Err(Error::new_spanned(
+            variant,
+            "AtomicCellStorable can only be derived for enums whose variants have no fields",
+        ));
+    }
+
+    let name = &input.ident;
+    let variants = data.variants.iter().map(|variant| &variant.ident);
+    Ok(quote! {
+        ::arc_cell::__storable_enum_impl!(#name { #(#variants),* });
+    })
+}
@@ -0,0 +1,162 @@
+use crate::{is_thin, AtomicCellConstInit, AtomicCellStorable, EMPTY_OPTION};
+use std::{
+    alloc::Allocator,
+    mem::{size_of, ManuallyDrop},
+    ptr::NonNull,
+    sync::Arc,
+};
+
+/// Whether the Arc fits in a raw value. A zero-sized allocator can be recreated out of thin
+/// air, anything else has to be boxed up together with the pointer.
+const fn is_direct<T: ?Sized, A>() -> bool {
+    is_thin::<T>() && size_of::<A>() == 0
+}
+
+fn arc_into_value<T: ?Sized, A: Allocator>(arc: Arc<T, A>) -> usize {
+    if is_direct::<T, A>() {
+        let (ptr, alloc) = Arc::into_raw_with_allocator(arc);
+        std::mem::forget(alloc);
+        unsafe { std::mem::transmute_copy::<*const T, usize>(&ptr) }
+    } else {
+        Box::into_raw(Box::new(arc)) as usize
+    }
+}
+
+unsafe fn arc_from_value<T: ?Sized, A: Allocator>(value: usize) -> Arc<T, A> {
+    if is_direct::<T, A>() {
+        // Reading a zero-sized value doesn't touch memory, this stands in for the allocator
+        // forgotten by `arc_into_value`.
+        let alloc = std::ptr::read(NonNull::<A>::dangling().as_ptr());
+        Arc::from_raw_in(std::mem::transmute_copy::<usize, *const T>(&value), alloc)
+    } else {
+        *Box::from_raw(value as *mut Arc<T, A>)
+    }
+}
+
+unsafe impl<T: ?Sized, A: Allocator> AtomicCellStorable for Arc<T, A> {
+    const TAKEN_VALUE: usize = usize::MAX;
+    const INDIRECT: bool = !is_direct::<T, A>();
+
+    fn into_value(self) -> usize {
+        arc_into_value(self)
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        arc_from_value(value)
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        if is_direct::<T, A>() {
+            ManuallyDrop::new(arc_from_value(value))
+        } else {
+            // The box keeps its Arc, so this copy doesn't own a reference.
+            ManuallyDrop::new(std::ptr::read(value as *const Self))
+        }
+    }
+
+    fn as_value(&self) -> usize {
+        // Only direct Arcs are their own raw value, this isn't used otherwise.
+        Arc::as_ptr(self) as *const () as usize
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        Arc::ptr_eq(self, other)
+    }
+}
+
+unsafe impl<T: ?Sized, A: Allocator> AtomicCellStorable for Option<Arc<T, A>> {
+    const TAKEN_VALUE: usize = <Arc<T, A> as AtomicCellStorable>::TAKEN_VALUE;
+    const INDIRECT: bool = <Arc<T, A> as AtomicCellStorable>::INDIRECT;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(arc) => arc_into_value(arc),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(arc_from_value(value)),
+        }
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(match value {
+            EMPTY_OPTION => None,
+            value => Some(ManuallyDrop::into_inner(
+                <Arc<T, A> as AtomicCellStorable>::peek_value(value),
+            )),
+        })
+    }
+
+    fn as_value(&self) -> usize {
+        self.as_ref().map_or(EMPTY_OPTION, Arc::as_value)
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(arc), Some(other)) => Arc::ptr_eq(arc, other),
+            (arc, other) => arc.is_none() && other.is_none(),
+        }
+    }
+}
+
+unsafe impl<T: ?Sized, A: Allocator> AtomicCellConstInit for Option<Arc<T, A>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AtomicCell;
+    use std::sync::{atomic::Ordering, Arc};
+
+    #[test]
+    fn custom_allocator() {
+        use std::alloc::{AllocError, Allocator, Global, Layout};
+        use std::ptr::NonNull;
+        use std::sync::atomic::AtomicUsize;
+
+        #[derive(Clone, Default)]
+        struct Arena;
+        unsafe impl Allocator for Arena {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        #[derive(Clone)]
+        struct Counting(Arc<AtomicUsize>);
+        unsafe impl Allocator for Counting {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Global.allocate(layout)
+            }
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                self.0.fetch_sub(1, Ordering::SeqCst);
+                Global.deallocate(ptr, layout)
+            }
+        }
+
+        let cell = AtomicCell::new(Arc::new_in(1, Arena));
+        let old = cell.set(Arc::new_in(2, Arena));
+        assert_eq!(*old, 1);
+        assert_eq!(*cell.get(), 2);
+        let current = cell.get();
+        assert!(cell
+            .compare_exchange(&current, Arc::new_in(3, Arena))
+            .is_ok());
+
+        let live = Arc::new(AtomicUsize::new(0));
+        let counting = Counting(live.clone());
+        let cell = AtomicCell::new(Some(Arc::new_in(4, counting.clone())));
+        assert_eq!(live.load(Ordering::SeqCst), 1);
+        assert_eq!(cell.get().as_deref(), Some(&4));
+        assert_eq!(cell.set(None).as_deref(), Some(&4));
+        assert_eq!(live.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,56 @@
+//! Storable impls for [`arcstr::ArcStr`].
+
+use crate::{AtomicCellConstInit, AtomicCellStorable, EMPTY_OPTION};
+use arcstr::ArcStr;
+use std::ptr::NonNull;
+
+unsafe impl AtomicCellStorable for ArcStr {
+    // ArcStr points to an aligned header, so this is never a valid pointer.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        ArcStr::into_raw(self).as_ptr() as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        ArcStr::from_raw(NonNull::new_unchecked(value as *mut ()))
+    }
+}
+
+unsafe impl AtomicCellStorable for Option<ArcStr> {
+    const TAKEN_VALUE: usize = <ArcStr as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(string) => string.into_value(),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(ArcStr::from_value(value)),
+        }
+    }
+}
+
+unsafe impl AtomicCellConstInit for Option<ArcStr> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn arcstr() {
+        use crate::{ArcStrCell, OptionalArcStrCell};
+
+        let cell = ArcStrCell::new(arcstr::literal!("static"));
+        assert_eq!(cell.set(arcstr::format!("{}-{}", "heap", 1)), "static");
+        assert_eq!(cell.get(), "heap-1");
+        static BANNER: OptionalArcStrCell = OptionalArcStrCell::const_new();
+        BANNER.set(Some(cell.get()));
+        assert_eq!(BANNER.take_some().as_deref(), Some("heap-1"));
+    }
+}
@@ -0,0 +1,69 @@
+//! `bincode` support, encoding cells transparently as a snapshot of their current value.
+
+use crate::{ArcCell, OptionalArcCell};
+use bincode::{
+    de::{BorrowDecode, BorrowDecoder, Decode, Decoder},
+    enc::{Encode, Encoder},
+    error::{DecodeError, EncodeError},
+};
+use std::sync::Arc;
+
+impl<T: Encode> Encode for ArcCell<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.get().encode(encoder)
+    }
+}
+
+impl<T: Encode> Encode for OptionalArcCell<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.get().as_deref().encode(encoder)
+    }
+}
+
+impl<Context, T: Decode<Context>> Decode<Context> for ArcCell<T> {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        T::decode(decoder).map(|value| ArcCell::new(Arc::new(value)))
+    }
+}
+
+impl<Context, T: Decode<Context>> Decode<Context> for OptionalArcCell<T> {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Option::<T>::decode(decoder).map(|value| OptionalArcCell::new(value.map(Arc::new)))
+    }
+}
+
+impl<'de, Context, T: BorrowDecode<'de, Context>> BorrowDecode<'de, Context> for ArcCell<T> {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        T::borrow_decode(decoder).map(|value| ArcCell::new(Arc::new(value)))
+    }
+}
+
+impl<'de, Context, T: BorrowDecode<'de, Context>> BorrowDecode<'de, Context>
+    for OptionalArcCell<T>
+{
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, DecodeError> {
+        Option::<T>::borrow_decode(decoder).map(|value| OptionalArcCell::new(value.map(Arc::new)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, OptionalArcCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn bincode() {
+        let config = bincode::config::standard();
+        let cell = ArcCell::new(Arc::new(7u32));
+        let optional = OptionalArcCell::new(Some(Arc::new(String::from("a"))));
+        let bytes = bincode::encode_to_vec((&cell, &optional), config).unwrap();
+        let ((cell, optional), _): ((ArcCell<u32>, OptionalArcCell<String>), _) =
+            bincode::decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(*cell.get(), 7);
+        assert_eq!(optional.get().as_deref().map(String::as_str), Some("a"));
+    }
+}
@@ -0,0 +1,50 @@
+//! `borsh` support, serializing cells transparently as a snapshot of their current value.
+
+use crate::{ArcCell, OptionalArcCell};
+use borsh::{
+    io::{Read, Result, Write},
+    BorshDeserialize, BorshSerialize,
+};
+use std::sync::Arc;
+
+impl<T: BorshSerialize> BorshSerialize for ArcCell<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.get().serialize(writer)
+    }
+}
+
+impl<T: BorshSerialize> BorshSerialize for OptionalArcCell<T> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        self.get().as_deref().serialize(writer)
+    }
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for ArcCell<T> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        T::deserialize_reader(reader).map(|value| ArcCell::new(Arc::new(value)))
+    }
+}
+
+impl<T: BorshDeserialize> BorshDeserialize for OptionalArcCell<T> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        Option::<T>::deserialize_reader(reader)
+            .map(|value| OptionalArcCell::new(value.map(Arc::new)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, OptionalArcCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn borsh() {
+        let cell = ArcCell::new(Arc::new(7u32));
+        let optional = OptionalArcCell::new(Some(Arc::new(String::from("a"))));
+        let bytes = borsh::to_vec(&(&cell, &optional)).unwrap();
+        let (cell, optional): (ArcCell<u32>, OptionalArcCell<String>) =
+            borsh::from_slice(&bytes).unwrap();
+        assert_eq!(*cell.get(), 7);
+        assert_eq!(optional.get().as_deref().map(String::as_str), Some("a"));
+    }
+}
@@ -0,0 +1,133 @@
+use crate::ArcCell;
+use std::{
+    fmt::{Debug, Formatter},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A per-thread copy of the value in an [`ArcCell`], for code that reads the same cell over and
+/// over again, like request handlers reading a config.
+///
+/// [`load`](Self::load) only loads the cell's raw pointer and compares it to the cached one, so
+/// as long as the value hasn't changed it doesn't take the value out of the cell or touch any
+/// reference counts. The cached Arc keeps its allocation alive, so a new value can never be
+/// mistaken for it.
+///
+/// `C` is anything that derefs to the cell, e.g. `&ArcCell<T>` or `Arc<ArcCell<T>>`.
+///
+/// ```
+/// use arc_cell::{ArcCell, Cache};
+/// use std::sync::Arc;
+///
+/// let config = ArcCell::new(Arc::new(String::from("v1")));
+/// let mut cache = Cache::new(&config);
+/// assert_eq!(**cache.load(), "v1");
+///
+/// config.set(Arc::new(String::from("v2")));
+/// assert_eq!(**cache.load(), "v2");
+/// ```
+pub struct Cache<C, T>
+where
+    C: Deref<Target = ArcCell<T>>,
+{
+    cell: C,
+    value: Arc<T>,
+}
+
+impl<C, T> Cache<C, T>
+where
+    C: Deref<Target = ArcCell<T>>,
+{
+    /// Create a new Cache of the value currently in `cell`.
+    pub fn new(cell: C) -> Self {
+        let value = cell.get();
+        Cache { cell, value }
+    }
+
+    /// Returns the value in the cell, reloading it only if the cell has changed since the last
+    /// call.
+    pub fn load(&mut self) -> &Arc<T> {
+        if !std::ptr::eq(self.cell.as_ptr(), Arc::as_ptr(&self.value)) {
+            self.value = self.cell.get();
+        }
+        &self.value
+    }
+
+    /// Returns the cached value without checking the cell for changes.
+    pub fn cached(&self) -> &Arc<T> {
+        &self.value
+    }
+
+    /// Returns the cell this cache reads from.
+    pub fn cell(&self) -> &C {
+        &self.cell
+    }
+
+    /// Consumes the cache, returning the cell it reads from.
+    pub fn into_cell(self) -> C {
+        self.cell
+    }
+}
+
+impl<C, T> Clone for Cache<C, T>
+where
+    C: Deref<Target = ArcCell<T>> + Clone,
+{
+    fn clone(&self) -> Self {
+        Cache {
+            cell: self.cell.clone(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<C, T> From<C> for Cache<C, T>
+where
+    C: Deref<Target = ArcCell<T>>,
+{
+    fn from(cell: C) -> Self {
+        Cache::new(cell)
+    }
+}
+
+impl<C, T: Debug> Debug for Cache<C, T>
+where
+    C: Deref<Target = ArcCell<T>>,
+{
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("Cache").field(&self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, Cache};
+    use std::sync::Arc;
+
+    #[test]
+    fn cache() {
+        let cell = Arc::new(ArcCell::new(Arc::new(1)));
+        let mut cache = Cache::new(cell.clone());
+        let first = cache.load().clone();
+        assert_eq!(Arc::strong_count(&first), 3);
+        for _ in 0..10 {
+            assert!(Arc::ptr_eq(cache.load(), &first));
+        }
+        assert_eq!(Arc::strong_count(&first), 3);
+
+        std::thread::spawn({
+            let cell = cell.clone();
+            move || cell.set(Arc::new(2))
+        })
+        .join()
+        .unwrap();
+        assert_eq!(**cache.cached(), 1);
+        assert_eq!(**cache.load(), 2);
+        assert_eq!(Arc::strong_count(&first), 1);
+
+        let mut other = cache.clone();
+        assert!(Arc::ptr_eq(other.cell(), &cell));
+        assert_eq!(format!("{:?}", other), "Cache(2)");
+        assert_eq!(**other.load(), 2);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::OptionalArcCell;
+use std::{
+    fmt::{Debug, Formatter},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A value read from a [`ConstantCell`], either its constant or an Arc stored in it later.
+pub enum MaybeStatic<T: 'static> {
+    /// The constant the cell was created with.
+    Static(&'static T),
+    /// An Arc that was stored in the cell.
+    Arc(Arc<T>),
+}
+
+impl<T> MaybeStatic<T> {
+    /// Returns whether this is the cell's constant.
+    pub fn is_static(&self) -> bool {
+        matches!(self, MaybeStatic::Static(_))
+    }
+
+    /// Returns the Arc, if this isn't the cell's constant.
+    pub fn into_arc(self) -> Option<Arc<T>> {
+        match self {
+            MaybeStatic::Static(_) => None,
+            MaybeStatic::Arc(arc) => Some(arc),
+        }
+    }
+}
+
+impl<T> Clone for MaybeStatic<T> {
+    fn clone(&self) -> Self {
+        match self {
+            MaybeStatic::Static(value) => MaybeStatic::Static(value),
+            MaybeStatic::Arc(arc) => MaybeStatic::Arc(arc.clone()),
+        }
+    }
+}
+
+impl<T> Deref for MaybeStatic<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            MaybeStatic::Static(value) => value,
+            MaybeStatic::Arc(arc) => arc,
+        }
+    }
+}
+
+impl<T: Debug> Debug for MaybeStatic<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        Debug::fmt(&**self, fmt)
+    }
+}
+
+/// An [`ArcCell`](crate::ArcCell)-like cell which starts out holding a `&'static T`, so it
+/// can be used in statics without any lazy initialization.
+///
+/// Arcs stored into it replace the constant, until [`reset`](Self::reset) switches it back.
+///
+/// ```
+/// use arc_cell::ConstantCell;
+/// use std::sync::Arc;
+///
+/// static LIMIT: ConstantCell<u32> = ConstantCell::new(&10);
+///
+/// assert_eq!(*LIMIT.get(), 10);
+/// LIMIT.set(Arc::new(20));
+/// assert_eq!(*LIMIT.get(), 20);
+/// ```
+pub struct ConstantCell<T: 'static> {
+    cell: OptionalArcCell<T>,
+    constant: &'static T,
+}
+
+impl<T> ConstantCell<T> {
+    /// Create a new ConstantCell holding `constant`.
+    pub const fn new(constant: &'static T) -> Self {
+        ConstantCell {
+            cell: OptionalArcCell::const_new(),
+            constant,
+        }
+    }
+
+    /// Returns the value currently held by the cell.
+    pub fn get(&self) -> MaybeStatic<T> {
+        self.wrap(self.cell.get())
+    }
+
+    /// Replace the value in the cell with an Arc, returning the old value.
+    pub fn set(&self, value: Arc<T>) -> MaybeStatic<T> {
+        self.wrap(self.cell.set(Some(value)))
+    }
+
+    /// Switch the cell back to holding its constant, returning the old value.
+    pub fn reset(&self) -> MaybeStatic<T> {
+        self.wrap(self.cell.set(None))
+    }
+
+    /// Returns whether the cell currently holds its constant.
+    pub fn is_constant(&self) -> bool {
+        self.cell.is_none()
+    }
+
+    /// Returns the constant the cell was created with.
+    pub fn constant(&self) -> &'static T {
+        self.constant
+    }
+
+    fn wrap(&self, value: Option<Arc<T>>) -> MaybeStatic<T> {
+        match value {
+            Some(arc) => MaybeStatic::Arc(arc),
+            None => MaybeStatic::Static(self.constant),
+        }
+    }
+}
+
+impl<T: Debug> Debug for ConstantCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("ConstantCell").field(&self.get()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ConstantCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn constant_cell() {
+        static CELL: ConstantCell<&str> = ConstantCell::new(&"default");
+        assert!(CELL.get().is_static());
+        assert_eq!(*CELL.set(Arc::new("custom")), "default");
+        assert!(!CELL.is_constant());
+        assert_eq!(CELL.reset().into_arc(), Some(Arc::new("custom")));
+        assert_eq!(*CELL.get(), "default");
+    }
+}
@@ -0,0 +1,36 @@
+//! `defmt` support, printing the stored value, or the pointer for Weak cells.
+
+use crate::{ArcCell, OptionalArcCell, OptionalWeakCell, WeakCell};
+use defmt::{Format, Formatter};
+
+impl<T: Format> Format for ArcCell<T> {
+    fn format(&self, fmt: Formatter) {
+        defmt::write!(fmt, "ArcCell({})", *self.get())
+    }
+}
+
+impl<T: Format> Format for OptionalArcCell<T> {
+    fn format(&self, fmt: Formatter) {
+        match self.get() {
+            Some(value) => defmt::write!(fmt, "OptionalArcCell({})", *value),
+            None => defmt::write!(fmt, "OptionalArcCell(<empty>)"),
+        }
+    }
+}
+
+impl<T> Format for WeakCell<T> {
+    fn format(&self, fmt: Formatter) {
+        defmt::write!(fmt, "WeakCell({=usize:#x})", self.as_ptr() as usize)
+    }
+}
+
+impl<T> Format for OptionalWeakCell<T> {
+    fn format(&self, fmt: Formatter) {
+        let ptr = self.as_ptr();
+        if ptr.is_null() {
+            defmt::write!(fmt, "OptionalWeakCell(<empty>)")
+        } else {
+            defmt::write!(fmt, "OptionalWeakCell({=usize:#x})", ptr as usize)
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::AtomicCellStorable;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+
+/// An Arc to one of two types, which is stored in an [`AtomicCell`](crate::AtomicCell) as a
+/// single pointer, using the lowest bit to tell the two apart.
+pub enum ArcEither<A, B> {
+    /// An Arc to the first type.
+    Left(Arc<A>),
+    /// An Arc to the second type.
+    Right(Arc<B>),
+}
+
+/// Set in the raw value of a `Right`. Arc pointers are always aligned to at least a `usize`,
+/// so this bit is otherwise never set.
+const RIGHT_TAG: usize = 1;
+
+impl<A, B> ArcEither<A, B> {
+    /// Returns whether this is a `Left`.
+    pub fn is_left(&self) -> bool {
+        matches!(self, ArcEither::Left(_))
+    }
+
+    /// Returns whether this is a `Right`.
+    pub fn is_right(&self) -> bool {
+        matches!(self, ArcEither::Right(_))
+    }
+
+    /// Returns the `Left` value, if this is one.
+    pub fn left(self) -> Option<Arc<A>> {
+        match self {
+            ArcEither::Left(left) => Some(left),
+            ArcEither::Right(_) => None,
+        }
+    }
+
+    /// Returns the `Right` value, if this is one.
+    pub fn right(self) -> Option<Arc<B>> {
+        match self {
+            ArcEither::Left(_) => None,
+            ArcEither::Right(right) => Some(right),
+        }
+    }
+
+    /// Returns a reference to the `Left` value, if this is one.
+    pub fn as_left(&self) -> Option<&Arc<A>> {
+        match self {
+            ArcEither::Left(left) => Some(left),
+            ArcEither::Right(_) => None,
+        }
+    }
+
+    /// Returns a reference to the `Right` value, if this is one.
+    pub fn as_right(&self) -> Option<&Arc<B>> {
+        match self {
+            ArcEither::Left(_) => None,
+            ArcEither::Right(right) => Some(right),
+        }
+    }
+}
+
+impl<A, B> Clone for ArcEither<A, B> {
+    fn clone(&self) -> Self {
+        match self {
+            ArcEither::Left(left) => ArcEither::Left(left.clone()),
+            ArcEither::Right(right) => ArcEither::Right(right.clone()),
+        }
+    }
+}
+
+impl<A: Debug, B: Debug> Debug for ArcEither<A, B> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ArcEither::Left(left) => fmt.debug_tuple("Left").field(left).finish(),
+            ArcEither::Right(right) => fmt.debug_tuple("Right").field(right).finish(),
+        }
+    }
+}
+
+unsafe impl<A, B> AtomicCellStorable for ArcEither<A, B> {
+    // Odd, but not aligned like the pointer in a `Right`.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        match self {
+            ArcEither::Left(left) => Arc::into_raw(left) as usize,
+            ArcEither::Right(right) => Arc::into_raw(right) as usize | RIGHT_TAG,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        if value & RIGHT_TAG == 0 {
+            ArcEither::Left(Arc::from_raw(value as *const A))
+        } else {
+            ArcEither::Right(Arc::from_raw((value & !RIGHT_TAG) as *const B))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcEither, AtomicCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn arc_either() {
+        struct Pending;
+        let cell = AtomicCell::new(ArcEither::<u8, Pending>::Right(Arc::new(Pending)));
+        assert!(cell.get().is_right());
+        let old = cell.set(ArcEither::Left(Arc::new(7)));
+        assert!(old.right().is_some());
+        assert_eq!(cell.get().as_left().map(|value| **value), Some(7));
+    }
+}
@@ -1,16 +1,91 @@
 #![doc = include_str!("../README.md")]
-#![cfg_attr(feature = "const-new", feature(const_fn_trait_bound))]
+#![cfg_attr(feature = "allocator-api", feature(allocator_api))]
+
+#[cfg(feature = "allocator-api")]
+mod allocator_impl;
+#[cfg(feature = "arcstr")]
+mod arcstr_impl;
+#[cfg(feature = "bincode")]
+mod bincode_impl;
+#[cfg(feature = "borsh")]
+mod borsh_impl;
+mod cache;
+mod constant;
+#[cfg(feature = "defmt")]
+mod defmt_impl;
+mod either;
+mod local;
+mod multi;
+mod nonmax;
+mod park;
+#[cfg(feature = "proptest")]
+mod proptest_impl;
+mod raw_ptr;
+mod read_mostly;
+#[cfg(feature = "rkyv")]
+mod rkyv_impl;
+#[cfg(feature = "schemars")]
+mod schemars_impl;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod small;
+#[cfg(feature = "stats")]
+mod stats;
+mod tagged;
+mod thin_arc;
+#[cfg(feature = "last-modified")]
+mod timestamp;
+#[cfg(feature = "triomphe")]
+mod triomphe_impl;
+mod versioned;
+mod waker;
+
+// Lets the derive macros refer to `::arc_cell` from inside this crate too.
+#[cfg(feature = "derive")]
+extern crate self as arc_cell;
+
+#[cfg(feature = "derive")]
+pub use arc_cell_derive::AtomicCellStorable;
+pub use cache::Cache;
+pub use constant::{ConstantCell, MaybeStatic};
+pub use either::ArcEither;
+pub use local::LocalCell;
+pub use multi::{publish2, publish3, set_all, snapshot2, snapshot3};
+pub use nonmax::{MaxValueError, NonMaxU32, NonMaxUsize};
+pub use raw_ptr::RawPtrCell;
+pub use read_mostly::{LoadGuard, ReadMostlyCell};
+#[cfg(feature = "rkyv")]
+pub use rkyv_impl::ArchivedArcCell;
+pub use small::{NoUninit, SmallCell};
+#[cfg(feature = "stats")]
+pub use stats::CellStats;
+pub use tagged::TaggedArc;
+pub use thin_arc::ThinArc;
+pub use versioned::VersionedCell;
+pub use waker::AtomicWakerCell;
 
 use std::{
-    fmt::{Debug, Formatter},
+    any::Any,
+    convert::Infallible,
+    fmt::{Debug, Display, Formatter},
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    pin::Pin,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, Weak,
     },
+    time::{Duration, Instant},
 };
 
 /// Atomically swappable/clonable Arc pointer value.
+///
+/// Unsized targets like `str`, `[T]` and `dyn Trait` have fat pointers that don't fit in the
+/// cell, so they are stored in a small box instead. Reads like `get` and `with` go through the
+/// box and put it back as it was, but every modification allocates a new box and frees the old
+/// one.
 pub type ArcCell<T> = AtomicCell<Arc<T>>;
 /// Atomically swappable/clonable Weak Arc pointer value.
 pub type WeakCell<T> = AtomicCell<Weak<T>>;
@@ -20,21 +95,133 @@ pub type OptionalArcCell<T> = AtomicCell<Option<Arc<T>>>;
 /// Atomically swappable/clonable/optional Weak Arc pointer value.
 pub type OptionalWeakCell<T> = AtomicCell<Option<Weak<T>>>;
 
+/// Atomically swappable/clonable [`triomphe::Arc`] pointer value.
+#[cfg(feature = "triomphe")]
+pub type TriompheCell<T> = AtomicCell<triomphe::Arc<T>>;
+/// Atomically swappable/clonable/optional [`triomphe::Arc`] pointer value.
+#[cfg(feature = "triomphe")]
+pub type OptionalTriompheCell<T> = AtomicCell<Option<triomphe::Arc<T>>>;
+
+/// Atomically swappable/clonable [`arcstr::ArcStr`] value.
+#[cfg(feature = "arcstr")]
+pub type ArcStrCell = AtomicCell<arcstr::ArcStr>;
+/// Atomically swappable/clonable/optional [`arcstr::ArcStr`] value.
+#[cfg(feature = "arcstr")]
+pub type OptionalArcStrCell = AtomicCell<Option<arcstr::ArcStr>>;
+
+/// Atomically swappable reference to a static value, e.g. one of several strategy objects.
+pub type StaticCell<T> = AtomicCell<&'static T>;
+
+/// Atomically swappable function pointer, e.g. `FnCell<fn(&str)>` for a replaceable hook.
+///
+/// Function pointers with up to six arguments can be stored, optionally wrapped in an
+/// `Option`. Arguments must be concrete types, so pointers that are generic over a lifetime
+/// such as `for<'a> fn(&'a str)` can't be stored, but `fn(&'static str)` can.
+pub type FnCell<F = fn()> = AtomicCell<F>;
+
+/// Atomically swappable small integer value, such as an epoch number or an index, which can
+/// be used alongside the pointer cells with the same API.
+pub type ValueCell<T = NonMaxUsize> = AtomicCell<T>;
+
+/// Atomically swappable result of a computation, holding either an Arc to the value or an
+/// [`ErrorCode`] packed into the same word.
+pub type ResultCell<T, E> = AtomicCell<Result<Arc<T>, E>>;
+
+/// Atomically swappable/clonable Arc pointer value with a tag of `BITS` bits packed into it,
+/// see [`TaggedArc`].
+pub type TaggedArcCell<T, const BITS: usize> = AtomicCell<TaggedArc<T, BITS>>;
+
+/// Atomically swappable/clonable/optional Arc pointer to a value of any type, with
+/// type-checked access through [`get_downcast`](AtomicCell::get_downcast).
+pub type AnyArcCell = AtomicCell<Option<Arc<dyn Any + Send + Sync>>>;
+
+/// Atomically swappable Box pointer value, for handing off values without reference counting.
+///
+/// Since a Box has a single owner, `get` clones the boxed value itself. Such a clone is never
+/// the same value as the stored Box for [`compare_exchange`](AtomicCell::compare_exchange),
+/// so updates like [`fetch_update`](AtomicCell::fetch_update) work on the value while it is
+/// taken instead.
+pub type BoxCell<T> = AtomicCell<Box<T>>;
+
+/// Atomically swappable optional Box pointer value, usable as a lock-free mailbox with
+/// [`set_if_none`](AtomicCell::set_if_none) and [`take_some`](AtomicCell::take_some).
+pub type OptionalBoxCell<T> = AtomicCell<Option<Box<T>>>;
+
+/// Atomically swappable/clonable Arc pointer value with a generation counter for ABA-safe
+/// compare-exchange, see [`VersionedCell::compare_exchange_version`].
+pub type GenArcCell<T> = VersionedCell<Arc<T>>;
+
 /// An atomic-based cell designed for holding Arc-style pointers.
+///
+/// Any thread with a reference to the cell can take its value out and drop it, so the cell is
+/// only `Sync` if `T` is both `Send` and `Sync`. A Box of a type that may be shared but not
+/// sent, like a `MutexGuard`, can't be handed to another thread through it:
+///
+/// ```compile_fail
+/// use arc_cell::OptionalBoxCell;
+/// use std::sync::MutexGuard;
+///
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<OptionalBoxCell<MutexGuard<'static, ()>>>();
+/// ```
 pub struct AtomicCell<T: AtomicCellStorable> {
     value: AtomicUsize,
-    _marker: PhantomData<T>,
+    #[cfg(feature = "last-modified")]
+    modified: std::sync::atomic::AtomicU64,
+    #[cfg(feature = "stats")]
+    stats: stats::Counters,
+    // The raw pointer opts out of the auto traits, which are implemented by hand below.
+    _marker: PhantomData<(T, *const ())>,
 }
 
+unsafe impl<T: AtomicCellStorable + Send> Send for AtomicCell<T> {}
+unsafe impl<T: AtomicCellStorable + Send + Sync> Sync for AtomicCell<T> {}
+
 impl<T: AtomicCellStorable> AtomicCell<T> {
     /// Create a new AtomicCell with the given initial value.
     pub fn new(value: T) -> Self {
+        unsafe { AtomicCell::from_raw(value.into_value()) }
+    }
+
+    /// Create a new AtomicCell from a raw value previously returned by [`into_raw`](Self::into_raw)
+    /// or [`AtomicCellStorable::into_value`].
+    ///
+    /// # Safety
+    ///
+    /// `value` must be a valid raw value for `T` as described by [`AtomicCellStorable::from_value`],
+    /// and ownership of it is transferred to the cell.
+    pub unsafe fn from_raw(value: usize) -> Self {
         AtomicCell {
-            value: AtomicUsize::new(value.into_value()),
+            value: AtomicUsize::new(value),
+            #[cfg(feature = "last-modified")]
+            modified: std::sync::atomic::AtomicU64::new(timestamp::now()),
+            #[cfg(feature = "stats")]
+            stats: stats::Counters::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Consumes the cell, returning the raw value of the stored value.
+    ///
+    /// Ownership of the value is transferred to the caller, who can turn it back into a cell
+    /// with [`from_raw`](Self::from_raw).
+    pub fn into_raw(self) -> usize {
+        self.into_inner().into_value()
+    }
+
+    /// Returns the atomic holding the raw value of the cell.
+    ///
+    /// While another thread is accessing the cell it holds `T::TAKEN_VALUE`.
+    ///
+    /// # Safety
+    ///
+    /// Any value stored into the atomic must uphold the same protocol the cell itself uses:
+    /// it must be a valid raw value for `T` owned by the cell, and a `TAKEN_VALUE` may only be
+    /// replaced by the thread that stored it.
+    pub unsafe fn as_atomic(&self) -> &AtomicUsize {
+        &self.value
+    }
+
     /// Replace the value in the cell, returning the old value.
     pub fn set(&self, value: T) -> T {
         let old = self.internal_take();
@@ -42,240 +229,3475 @@ impl<T: AtomicCellStorable> AtomicCell<T> {
         old
     }
 
-    fn internal_take(&self) -> T {
-        unsafe {
-            let mut current = self.value.load(Ordering::SeqCst);
-            T::from_value(loop {
-                // Try to take it ourselves
-                match self.value.compare_exchange_weak(
-                    current,
-                    T::TAKEN_VALUE,
-                    Ordering::SeqCst,
-                    Ordering::SeqCst,
-                ) {
-                    Ok(val) if val != T::TAKEN_VALUE => break val,
-                    Ok(_) => current = T::TAKEN_VALUE, // Someone else was working on it, retry
-                    Err(new_val) => current = new_val, // Someone got to it first, retry
+    /// Like [`set`](Self::set), but with explicit memory orderings for taking the old value out
+    /// of the cell and for putting the new value in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `take` is weaker than [`Acquire`](Ordering::Acquire) or `put` is weaker than
+    /// [`Release`](Ordering::Release), since either would allow the pointed-to data to be read
+    /// before it was written.
+    pub fn set_with(&self, value: T, take: Ordering, put: Ordering) -> T {
+        // Check this up front so we can't panic while the value is taken.
+        put_ordering(put);
+        let old = self.internal_take_with(take);
+        self.internal_store_taken(value, put);
+        self.notify();
+        old
+    }
+
+    /// Atomically replaces the value with the result of calling `f` on it, returning the old
+    /// value, like [`RefCell::replace_with`](std::cell::RefCell::replace_with).
+    ///
+    /// The value stays taken while `f` runs, and `f` can move parts of it into the new value,
+    /// for example with [`std::mem::take`], to reuse an allocation. If `f` panics, the old value
+    /// is put back along with any changes `f` made to it.
+    pub fn replace_with<F>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut T) -> T,
+    {
+        let mut value = self.internal_lock();
+        let new = f(&mut value);
+        std::mem::replace(&mut *value, new)
+    }
+
+    /// Temporarily replace the value in the cell, restoring the old value when the returned
+    /// guard is dropped.
+    ///
+    /// The old value is restored even if the thread panics, and whatever value the cell holds
+    /// at that point is dropped.
+    pub fn override_scoped(&self, value: T) -> OverrideGuard<'_, T> {
+        OverrideGuard {
+            previous: Some(self.set(value)),
+            cell: self,
+        }
+    }
+
+    /// Replace the value in the cell, returning the old value, unless another thread currently
+    /// has it taken.
+    ///
+    /// Unlike [`set`](Self::set) this never spins waiting for other threads, and instead hands
+    /// `value` back as the error.
+    pub fn try_set(&self, value: T) -> Result<T, T> {
+        match self.internal_try_take() {
+            Some(old) => {
+                self.internal_put(value);
+                Ok(old)
+            }
+            None => Err(value),
+        }
+    }
+
+    /// Replace the value in the cell, returning the old value, giving up if another thread
+    /// keeps it taken for longer than `timeout`.
+    pub fn set_timeout(&self, value: T, timeout: Duration) -> Result<T, TimeoutError<T>> {
+        match self.internal_take_timeout(timeout) {
+            Some(old) => {
+                self.internal_put(value);
+                Ok(old)
+            }
+            None => Err(TimeoutError(value)),
+        }
+    }
+
+    /// Stores `new` into the cell if the cell currently holds the same value as `current`.
+    ///
+    /// Values are compared by their raw representation, which for the pointer types in this
+    /// crate means pointer identity. On success the previous value is returned, otherwise `new`
+    /// is handed back to the caller. If another thread is in the middle of accessing the cell
+    /// this will wait for it to finish before comparing.
+    pub fn compare_exchange(&self, current: &T, new: T) -> Result<T, T> {
+        if T::INDIRECT {
+            let value = self.internal_take();
+            return self.internal_exchange_taken(value, current, new);
+        }
+        self.internal_compare_exchange(current.as_value(), new)
+    }
+
+    /// Compares the value taken out of the cell with `current`, putting either `new` or the
+    /// value back.
+    fn internal_exchange_taken(&self, value: T, current: &T, new: T) -> Result<T, T> {
+        if value.same_as(current) {
+            self.internal_put(new);
+            Ok(value)
+        } else {
+            self.internal_restore(value);
+            Err(new)
+        }
+    }
+
+    fn internal_compare_exchange(&self, current: usize, new: T) -> Result<T, T> {
+        // Never "succeed" at replacing a value another thread has taken.
+        if current == T::TAKEN_VALUE {
+            return Err(new);
+        }
+
+        let new = new.into_value();
+        loop {
+            match self
+                .value
+                .compare_exchange(current, new, Ordering::SeqCst, Ordering::SeqCst)
+            {
+                Ok(old) => {
+                    self.notify();
+                    return Ok(unsafe { T::from_value(old) });
                 }
+                Err(val) if val == T::TAKEN_VALUE => self.spin(),
+                Err(_) => return Err(unsafe { T::from_value(new) }),
+            }
+        }
+    }
 
-                // Hint to the CPU we're in a spin loop to reduce power consumption and allow
-                // another hyperthread to possibly start.
-                core::hint::spin_loop();
-            })
+    /// Like [`compare_exchange`](Self::compare_exchange), but allowed to fail spuriously.
+    ///
+    /// This never waits for other threads to finish accessing the cell, and may compile to a
+    /// cheaper instruction sequence on some platforms. It is intended for use in loops that
+    /// retry on failure anyway.
+    pub fn compare_exchange_weak(&self, current: &T, new: T) -> Result<T, T> {
+        if T::INDIRECT {
+            return match self.internal_try_take() {
+                Some(value) => self.internal_exchange_taken(value, current, new),
+                None => Err(new),
+            };
+        }
+        let current = current.as_value();
+        let new = new.into_value();
+        match self
+            .value
+            .compare_exchange_weak(current, new, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(old) => {
+                self.notify();
+                Ok(unsafe { T::from_value(old) })
+            }
+            Err(_) => Err(unsafe { T::from_value(new) }),
+        }
+    }
+
+    /// Calls `f` with a reference to the stored value, without cloning it.
+    ///
+    /// The value stays taken out of the cell while `f` runs, so other threads accessing the
+    /// cell will spin until it returns and `f` must not access the cell itself. The value is
+    /// put back even if `f` panics.
+    pub fn with<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        f(&self.internal_peek())
+    }
+
+    /// Exchanges the values stored in the two cells.
+    ///
+    /// Both cells are taken before either is put back, so no thread can observe the same value
+    /// in both cells. The cells are always taken in order of their addresses, which means two
+    /// threads swapping the same pair in opposite directions won't deadlock.
+    pub fn swap_with(&self, other: &AtomicCell<T>) {
+        if std::ptr::eq(self, other) {
+            return;
+        }
+
+        let (first, second) = if (self as *const Self) < (other as *const Self) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let first_value = first.internal_take();
+        let second_value = second.internal_take();
+        first.internal_put(second_value);
+        second.internal_put(first_value);
+    }
+
+    /// Returns whether both cells currently hold the same value.
+    ///
+    /// For the pointer types in this crate this compares pointer identity, without taking the
+    /// values out of either cell. The two cells are read one after the other, so the result is
+    /// only a snapshot if either is being modified concurrently.
+    ///
+    /// Indirect storables like `Arc<str>` get a new raw value every time they are stored, so
+    /// for those both cells are taken, in order of their addresses, and compared with
+    /// [`same_as`](AtomicCellStorable::same_as).
+    pub fn same_value(&self, other: &AtomicCell<T>) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        if !T::INDIRECT {
+            return self.internal_load() == other.internal_load();
+        }
+
+        let (first, second) = if (self as *const Self) < (other as *const Self) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let first_value = first.internal_take();
+        let second_value = second.internal_take();
+        let same = first_value.same_as(&second_value);
+        second.internal_restore(second_value);
+        first.internal_restore(first_value);
+        same
+    }
+
+    /// Returns whether another thread currently has the value taken out of the cell.
+    ///
+    /// This is purely a hint, the answer may already be out of date when it is returned.
+    pub fn is_contended(&self) -> bool {
+        self.value.load(Ordering::Relaxed) == T::TAKEN_VALUE
+    }
+
+    /// Returns exclusive access to the stored value without any atomic operations.
+    ///
+    /// The value is written back into the cell when the returned [`ValueMut`] is dropped. If
+    /// it is leaked instead, the value is leaked with it and the cell is left permanently taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell was left taken by a leaked [`ValueMut`].
+    pub fn get_mut(&mut self) -> ValueMut<'_, T> {
+        let value = *self.value.get_mut();
+        assert_ne!(value, T::TAKEN_VALUE, "AtomicCell was left taken");
+        *self.value.get_mut() = T::TAKEN_VALUE;
+        ValueMut {
+            value: ManuallyDrop::new(unsafe { T::from_value(value) }),
+            cell: self,
+        }
+    }
+
+    /// Consumes the cell, returning the stored value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell was left taken by a leaked [`ValueMut`].
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        let value = *this.value.get_mut();
+        assert_ne!(value, T::TAKEN_VALUE, "AtomicCell was left taken");
+        unsafe { T::from_value(value) }
+    }
+
+    fn internal_take(&self) -> T {
+        self.internal_take_with(Ordering::SeqCst)
+    }
+
+    fn internal_take_with(&self, order: Ordering) -> T {
+        unsafe { T::from_value(self.internal_take_raw_with(order)) }
+    }
+
+    /// Takes the raw value, leaving it to the caller to convert or put back.
+    fn internal_take_raw_with(&self, order: Ordering) -> usize {
+        let (success, failure) = take_orderings(order);
+        let mut current = self.value.load(failure);
+        loop {
+            // Try to take it ourselves
+            match self
+                .value
+                .compare_exchange_weak(current, T::TAKEN_VALUE, success, failure)
+            {
+                Ok(val) if val != T::TAKEN_VALUE => break val,
+                Ok(_) => current = T::TAKEN_VALUE, // Someone else was working on it, retry
+                Err(new_val) => current = new_val, // Someone got to it first, retry
+            }
+
+            self.spin();
+        }
+    }
+
+    /// Takes the value unless another thread already has it taken.
+    fn internal_try_take(&self) -> Option<T> {
+        self.internal_try_take_raw()
+            .map(|value| unsafe { T::from_value(value) })
+    }
+
+    fn internal_try_take_raw(&self) -> Option<usize> {
+        let mut current = self.value.load(Ordering::SeqCst);
+        while current != T::TAKEN_VALUE {
+            match self.value.compare_exchange_weak(
+                current,
+                T::TAKEN_VALUE,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(val) => return Some(val),
+                Err(new_val) => current = new_val,
+            }
+        }
+        None
+    }
+
+    /// Takes the value, giving up if it stays taken by other threads for longer than `timeout`.
+    fn internal_take_timeout(&self, timeout: Duration) -> Option<T> {
+        self.internal_take_raw_timeout(timeout)
+            .map(|value| unsafe { T::from_value(value) })
+    }
+
+    fn internal_take_raw_timeout(&self, timeout: Duration) -> Option<usize> {
+        let deadline = Instant::now().checked_add(timeout);
+        loop {
+            if let Some(value) = self.internal_try_take_raw() {
+                break Some(value);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break None;
+            }
+            self.spin();
+        }
+    }
+
+    /// Takes the value, putting it back when the returned guard is dropped.
+    fn internal_lock(&self) -> Taken<'_, T> {
+        self.internal_guard(self.internal_take(), Ordering::SeqCst)
+    }
+
+    /// Takes the value for reading, putting the same raw value back when the returned guard is
+    /// dropped.
+    fn internal_peek(&self) -> Peeked<'_, T> {
+        self.internal_peek_guard(
+            self.internal_take_raw_with(Ordering::SeqCst),
+            Ordering::SeqCst,
+        )
+    }
+
+    /// Wraps a raw value taken out of the cell so it can be read, and put back with `restore`
+    /// as the ordering when dropped.
+    fn internal_peek_guard(&self, raw: usize, restore: Ordering) -> Peeked<'_, T> {
+        let value = if T::INDIRECT {
+            unsafe { T::peek_value(raw) }
+        } else {
+            ManuallyDrop::new(unsafe { T::from_value(raw) })
+        };
+        Peeked {
+            cell: self,
+            raw,
+            value,
+            restore,
+        }
+    }
+
+    /// Wraps a value taken out of the cell so it is put back when dropped, with `restore` as
+    /// the ordering if it wasn't modified.
+    fn internal_guard(&self, value: T, restore: Ordering) -> Taken<'_, T> {
+        Taken {
+            value: ManuallyDrop::new(value),
+            cell: self,
+            modified: false,
+            restore,
+        }
+    }
+
+    /// Loads the raw value, waiting for any thread that has it taken to put it back.
+    fn internal_load(&self) -> usize {
+        loop {
+            let value = self.value.load(Ordering::SeqCst);
+            if value != T::TAKEN_VALUE {
+                break value;
+            }
+            self.spin();
         }
     }
 
+    /// Puts a new value into the taken cell, waking any threads waiting for it to change.
     fn internal_put(&self, value: T) {
-        let _old = self.value.swap(value.into_value(), Ordering::SeqCst);
+        self.internal_store_taken(value, Ordering::SeqCst);
+        self.notify();
+    }
+
+    /// Puts the value that was taken back into the cell.
+    fn internal_restore(&self, value: T) {
+        self.internal_restore_with(value, Ordering::SeqCst);
+    }
+
+    fn internal_restore_with(&self, value: T, order: Ordering) {
+        #[cfg(feature = "stats")]
+        self.stats.get();
+        self.internal_store_taken(value, order);
+    }
+
+    fn internal_store_taken(&self, value: T, order: Ordering) {
+        let _old = self.value.swap(value.into_value(), put_ordering(order));
         debug_assert_eq!(_old, T::TAKEN_VALUE);
     }
+
+    /// Called whenever a thread has to wait for another thread to put the value back.
+    fn spin(&self) {
+        #[cfg(feature = "stats")]
+        self.stats.retry();
+        // Hint to the CPU we're in a spin loop to reduce power consumption and allow
+        // another hyperthread to possibly start.
+        core::hint::spin_loop();
+    }
+
+    /// Called after every modification of the cell.
+    fn notify(&self) {
+        #[cfg(feature = "stats")]
+        self.stats.set();
+        #[cfg(feature = "last-modified")]
+        self.modified.store(timestamp::now(), Ordering::Relaxed);
+        park::notify(self as *const Self as usize);
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<T: AtomicCellStorable> AtomicCell<T> {
+    /// Returns how often the cell has been read, modified, and contended since it was created.
+    ///
+    /// Counters are updated with relaxed atomics, so a snapshot taken while other threads are
+    /// using the cell may be slightly out of date.
+    pub fn stats(&self) -> CellStats {
+        self.stats.snapshot()
+    }
+}
+
+#[cfg(feature = "last-modified")]
+impl<T: AtomicCellStorable> AtomicCell<T> {
+    /// Returns when a value was last stored in the cell.
+    ///
+    /// Creating the cell counts as storing its initial value. Cells created with `const_new`
+    /// that haven't been modified yet report the first time any cell's timestamp was taken.
+    pub fn last_modified(&self) -> Instant {
+        timestamp::to_instant(self.modified.load(Ordering::Relaxed))
+    }
+
+    /// Returns how long ago a value was last stored in the cell, see
+    /// [`last_modified`](Self::last_modified).
+    pub fn age(&self) -> Duration {
+        self.last_modified().elapsed()
+    }
 }
 
 impl<T: AtomicCellStorable> Drop for AtomicCell<T> {
     fn drop(&mut self) {
-        unsafe {
-            let _ = T::from_value(self.value.load(Ordering::SeqCst));
+        let value = *self.value.get_mut();
+        // The cell can only be taken here if a `ValueMut` was leaked.
+        if value != T::TAKEN_VALUE {
+            unsafe {
+                let _ = T::from_value(value);
+            }
         }
     }
 }
 
-impl<T: AtomicCellStorable + Clone> AtomicCell<T> {
-    /// Returns a clone of the stored value.
-    pub fn get(&self) -> T {
-        let value = self.internal_take();
-        let copy = value.clone();
-        self.internal_put(value);
-        copy
+/// The error returned when a timed operation couldn't access a cell before the timeout expired.
+///
+/// Contains the value that couldn't be stored, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError<T = ()>(pub T);
+
+impl<T> TimeoutError<T> {
+    /// Returns the value that couldn't be stored.
+    pub fn into_inner(self) -> T {
+        self.0
     }
 }
 
-impl<T: AtomicCellStorable + Clone> Clone for AtomicCell<T> {
-    fn clone(&self) -> AtomicCell<T> {
-        AtomicCell::new(self.get())
+impl<T> Display for TimeoutError<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.write_str("timed out waiting for the cell")
     }
 }
 
-impl<T: AtomicCellStorable + Default> AtomicCell<T> {
-    /// Take the value stored in the cell, replacing it with the default value.
-    pub fn take(&self) -> T {
-        // We must construct the new value first in case it panics.
-        let new_value = T::default();
+impl<T: Debug> std::error::Error for TimeoutError<T> {}
 
-        let value = self.internal_take();
-        self.internal_put(new_value);
+/// Remembers the last value seen by [`AtomicCell::load_if_changed`].
+///
+/// The token holds a Weak pointer to that value, which keeps its allocation from being freed and
+/// reused for a new value that would then be mistaken for the old one.
+pub struct ChangeToken<T> {
+    seen: Weak<T>,
+}
 
-        value
+impl<T> ChangeToken<T> {
+    /// Create a new token which hasn't seen any value yet.
+    pub fn new() -> Self {
+        ChangeToken { seen: Weak::new() }
     }
 }
 
-impl<T: AtomicCellStorable + Default> Default for AtomicCell<T> {
+impl<T> Default for ChangeToken<T> {
     fn default() -> Self {
-        AtomicCell::new(T::default())
+        ChangeToken::new()
     }
 }
 
-#[cfg(feature = "const-new")]
-impl<T: AtomicCellStorable + AtomicCellConstInit> AtomicCell<T> {
-    pub const fn const_new() -> Self {
-        AtomicCell {
-            value: AtomicUsize::new(T::DEFAULT_VALUE),
-            _marker: PhantomData,
-        }
+impl<T> Debug for ChangeToken<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("ChangeToken")
+            .field(&self.seen.as_ptr())
+            .finish()
     }
 }
 
-impl<T> AtomicCell<Weak<T>> {
-    /// Create a new AtomicCell with an empty Weak<T> stored inside.
-    pub fn empty() -> Self {
-        AtomicCell::new(Weak::new())
+/// Returns the success and failure orderings to use when taking a value with `order`.
+fn take_orderings(order: Ordering) -> (Ordering, Ordering) {
+    match order {
+        Ordering::SeqCst => (Ordering::SeqCst, Ordering::SeqCst),
+        Ordering::Acquire | Ordering::AcqRel => (order, Ordering::Relaxed),
+        _ => panic!("values must be taken with at least Acquire ordering"),
     }
+}
 
-    /// Attempt to upgrade the Weak pointer to a strong Arc pointer.
-    pub fn upgrade(&self) -> Option<Arc<T>> {
-        self.get().upgrade()
+/// Checks that `order` is strong enough to put a value back into a cell.
+fn put_ordering(order: Ordering) -> Ordering {
+    match order {
+        Ordering::SeqCst | Ordering::Release | Ordering::AcqRel => order,
+        _ => panic!("values must be put back with at least Release ordering"),
     }
+}
 
-    /// Downgrade the Arc value and store it in the cell.
-    pub fn store(&self, arc: &Arc<T>) {
-        self.set(Arc::downgrade(arc));
-    }
+/// A value taken out of a cell, which is put back when dropped (including during a panic).
+struct Taken<'a, T: AtomicCellStorable> {
+    cell: &'a AtomicCell<T>,
+    value: ManuallyDrop<T>,
+    modified: bool,
+    restore: Ordering,
 }
 
-impl<T> AtomicCell<Option<Weak<T>>> {
-    /// Attempt to upgrade the Weak pointer to a strong Arc pointer (if it is not None).
-    pub fn upgrade(&self) -> Option<Arc<T>> {
-        self.get().and_then(|weak| weak.upgrade())
+impl<T: AtomicCellStorable> Deref for Taken<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
     }
+}
 
-    /// Downgrade the Arc value and store it in the cell.
-    pub fn store(&self, arc: &Arc<T>) {
-        self.set(Some(Arc::downgrade(arc)));
+impl<T: AtomicCellStorable> DerefMut for Taken<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.modified = true;
+        &mut self.value
     }
 }
 
-impl<T: AtomicCellStorable + Clone + Debug> Debug for AtomicCell<T> {
-    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
-        fmt.debug_tuple("AtomicCell").field(&self.get()).finish()
+impl<T: AtomicCellStorable> Drop for Taken<'_, T> {
+    fn drop(&mut self) {
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        if self.modified {
+            self.cell.internal_put(value);
+        } else {
+            self.cell.internal_restore_with(value, self.restore);
+        }
     }
 }
 
-/// It is up to the implementer to ensure this is safe to implement.
-///
-/// `from_value` and `into_value` should never panic nor return TAKEN_VALUE.
-/// It is also up to the implementer to ensure that if T implements Clone,
-/// its implementation of clone() will never panic.
-pub unsafe trait AtomicCellStorable {
-    /// A sentinel value that a valid instance should never occupy.
-    const TAKEN_VALUE: usize;
-    /// Convert an instance into a raw value, transferring ownership.
-    fn into_value(self) -> usize;
-    /// Convert a raw value back into an instance.
-    unsafe fn from_value(value: usize) -> Self;
+/// A value taken out of a cell only to be read, which is put back when dropped (including during
+/// a panic). Indirect values are read through their raw value, which then goes back into the
+/// cell as it was instead of being boxed up again.
+struct Peeked<'a, T: AtomicCellStorable> {
+    cell: &'a AtomicCell<T>,
+    raw: usize,
+    value: ManuallyDrop<T>,
+    restore: Ordering,
 }
 
-unsafe impl<T> AtomicCellStorable for Arc<T> {
-    const TAKEN_VALUE: usize = usize::MAX;
+impl<T: AtomicCellStorable> Deref for Peeked<'_, T> {
+    type Target = T;
 
-    fn into_value(self) -> usize {
-        Arc::into_raw(self) as usize
+    fn deref(&self) -> &T {
+        &self.value
     }
+}
 
-    unsafe fn from_value(value: usize) -> Self {
-        Arc::from_raw(value as *const T)
+impl<T: AtomicCellStorable> Drop for Peeked<'_, T> {
+    fn drop(&mut self) {
+        #[cfg(feature = "stats")]
+        self.cell.stats.get();
+        let raw = if T::INDIRECT {
+            self.raw
+        } else {
+            unsafe { ManuallyDrop::take(&mut self.value) }.into_value()
+        };
+        let _old = self.cell.value.swap(raw, put_ordering(self.restore));
+        debug_assert_eq!(_old, T::TAKEN_VALUE);
     }
 }
 
-unsafe impl<T> AtomicCellStorable for Weak<T> {
-    // This must be MAX-1 because MAX is the sentinel value Weak uses for the empty state.
-    const TAKEN_VALUE: usize = usize::MAX - 1;
+/// Restores the previous value of a cell when dropped, see [`AtomicCell::override_scoped`].
+#[must_use = "the previous value is restored as soon as the guard is dropped"]
+pub struct OverrideGuard<'a, T: AtomicCellStorable> {
+    cell: &'a AtomicCell<T>,
+    previous: Option<T>,
+}
 
-    fn into_value(self) -> usize {
-        Weak::into_raw(self) as usize
+impl<T: AtomicCellStorable> Drop for OverrideGuard<'_, T> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.cell.set(previous);
+        }
     }
+}
 
-    unsafe fn from_value(value: usize) -> Self {
-        Weak::from_raw(value as *const T)
+impl<T: AtomicCellStorable + Debug> Debug for OverrideGuard<'_, T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("OverrideGuard")
+            .field("previous", &self.previous)
+            .finish_non_exhaustive()
     }
 }
 
-const EMPTY_OPTION: usize = 0;
+/// Exclusive access to the value stored in an [`AtomicCell`], see [`AtomicCell::get_mut`].
+pub struct ValueMut<'a, T: AtomicCellStorable> {
+    cell: &'a mut AtomicCell<T>,
+    value: ManuallyDrop<T>,
+}
 
-unsafe impl<T> AtomicCellStorable for Option<Arc<T>> {
-    const TAKEN_VALUE: usize = <Arc<T> as AtomicCellStorable>::TAKEN_VALUE;
+impl<T: AtomicCellStorable> Deref for ValueMut<'_, T> {
+    type Target = T;
 
-    fn into_value(self) -> usize {
-        match self {
-            None => EMPTY_OPTION,
-            Some(arc) => Arc::into_raw(arc) as usize,
-        }
+    fn deref(&self) -> &T {
+        &self.value
     }
+}
 
-    unsafe fn from_value(value: usize) -> Self {
-        match value {
-            EMPTY_OPTION => None,
-            value => Some(Arc::from_raw(value as *const T)),
-        }
+impl<T: AtomicCellStorable> DerefMut for ValueMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
     }
 }
 
-unsafe impl<T> AtomicCellStorable for Option<Weak<T>> {
-    const TAKEN_VALUE: usize = <Weak<T> as AtomicCellStorable>::TAKEN_VALUE;
-
-    fn into_value(self) -> usize {
-        match self {
-            None => EMPTY_OPTION,
-            Some(arc) => Weak::into_raw(arc) as usize,
+impl<T: AtomicCellStorable> Drop for ValueMut<'_, T> {
+    fn drop(&mut self) {
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        *self.cell.value.get_mut() = value.into_value();
+        #[cfg(feature = "stats")]
+        self.cell.stats.set();
+        #[cfg(feature = "last-modified")]
+        {
+            *self.cell.modified.get_mut() = timestamp::now();
         }
     }
+}
 
-    unsafe fn from_value(value: usize) -> Self {
-        match value {
-            EMPTY_OPTION => None,
-            value => Some(Weak::from_raw(value as *const T)),
-        }
+impl<T: AtomicCellStorable + Debug> Debug for ValueMut<'_, T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        Debug::fmt(&**self, fmt)
     }
 }
 
-pub unsafe trait AtomicCellConstInit {
-    const DEFAULT_VALUE: usize;
+/// Shared access to the value stored in an [`ArcCell`], see [`AtomicCell::read`].
+///
+/// The guard holds its own reference to the value, so the cell can be modified while it is
+/// alive and it keeps seeing the value that was read.
+pub struct ReadGuard<'a, T> {
+    value: Arc<T>,
+    _cell: PhantomData<&'a ArcCell<T>>,
 }
 
-unsafe impl<T> AtomicCellConstInit for Option<Arc<T>> {
-    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+impl<T> ReadGuard<'_, T> {
+    /// Returns the Arc held by the guard.
+    pub fn into_arc(this: Self) -> Arc<T> {
+        this.value
+    }
 }
 
-unsafe impl<T> AtomicCellConstInit for Option<Weak<T>> {
-    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::{ArcCell, WeakCell};
-    use std::sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    };
+impl<T: Debug> Debug for ReadGuard<'_, T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        Debug::fmt(&**self, fmt)
+    }
+}
+
+impl<T: AtomicCellStorable + Clone> AtomicCell<T> {
+    /// Returns a clone of the stored value.
+    ///
+    /// The value is taken out of the cell while it is cloned, so concurrent calls take turns.
+    /// [`ReadMostlyCell`] is better suited for hot data that is read far more often than it is
+    /// written. The value is put back even if its `clone` panics.
+    pub fn get(&self) -> T {
+        T::clone(&self.internal_peek())
+    }
+
+    /// Like [`get`](Self::get), but with an explicit memory ordering for taking the value.
+    ///
+    /// The value is put back with [`Release`](Ordering::Release) ordering, or
+    /// [`SeqCst`](Ordering::SeqCst) if that is what `order` is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `order` is weaker than [`Acquire`](Ordering::Acquire), since that would allow
+    /// the pointed-to data to be read before it was written.
+    pub fn get_with(&self, order: Ordering) -> T {
+        let value = self.internal_take_raw_with(order);
+        let put = match order {
+            Ordering::SeqCst => Ordering::SeqCst,
+            _ => Ordering::Release,
+        };
+        T::clone(&self.internal_peek_guard(value, put))
+    }
+
+    /// Returns a clone of the stored value, or `None` if another thread currently has it taken.
+    ///
+    /// Unlike [`get`](Self::get) this never spins waiting for other threads.
+    pub fn try_get(&self) -> Option<T> {
+        let value = self.internal_try_take_raw()?;
+        Some(T::clone(&self.internal_peek_guard(value, Ordering::SeqCst)))
+    }
+
+    /// Returns a clone of the stored value, giving up if another thread keeps it taken for
+    /// longer than `timeout`.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<T, TimeoutError> {
+        let value = self
+            .internal_take_raw_timeout(timeout)
+            .ok_or(TimeoutError(()))?;
+        Ok(T::clone(&self.internal_peek_guard(value, Ordering::SeqCst)))
+    }
+
+    /// Returns a clone of the stored value if it satisfies `pred`.
+    ///
+    /// As with [`with`](Self::with), the value stays taken while `pred` runs.
+    pub fn get_if<F>(&self, pred: F) -> Option<T>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        self.with(|value| {
+            if pred(value) {
+                Some(value.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Blocks the current thread until the stored value satisfies `pred`, returning a clone of it.
+    ///
+    /// The thread is parked rather than spinning, and `pred` is re-checked every time the cell
+    /// is modified.
+    pub fn wait_until<F>(&self, pred: F) -> T
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut result = None;
+        park::wait_until(self as *const Self as usize, || {
+            result = self.get_if(&pred);
+            result.is_some()
+        });
+        result.unwrap()
+    }
+
+    /// Repeatedly applies `f` to the current value until its result is stored atomically.
+    ///
+    /// If `f` returns `None` the cell is left unchanged and `Err` is returned with the value
+    /// that was passed to it, otherwise `Ok` is returned with the value that was replaced.
+    /// `f` may be called multiple times if other threads modify the cell concurrently.
+    ///
+    /// For types whose clones aren't the same value as the original, see
+    /// [`AtomicCellStorable::CLONES_ARE_SAME`], `f` is called once while the value is taken.
+    pub fn fetch_update<F>(&self, mut f: F) -> Result<T, T>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        if !T::CLONES_ARE_SAME {
+            let mut value = self.internal_lock();
+            return match f(&value) {
+                Some(new) => Ok(std::mem::replace(&mut *value, new)),
+                None => Err(T::clone(&value)),
+            };
+        }
+        let mut current = self.get();
+        while let Some(new) = f(&current) {
+            match self.compare_exchange_weak(&current, new) {
+                Ok(old) => return Ok(old),
+                Err(_) => current = self.get(),
+            }
+        }
+        Err(current)
+    }
+
+    /// Atomically replaces the value with the result of `f`, returning the previous value.
+    ///
+    /// `f` may be called multiple times if other threads modify the cell concurrently.
+    pub fn get_and_update<F>(&self, mut f: F) -> T
+    where
+        F: FnMut(&T) -> T,
+    {
+        match self.fetch_update(|current| Some(f(current))) {
+            Ok(old) | Err(old) => old,
+        }
+    }
+
+    /// Atomically replaces the value with the result of `f`, returning the new value.
+    ///
+    /// `f` may be called multiple times if other threads modify the cell concurrently, except
+    /// for types without [`CLONES_ARE_SAME`](AtomicCellStorable::CLONES_ARE_SAME), as in
+    /// [`fetch_update`](Self::fetch_update).
+    pub fn update_and_get<F>(&self, mut f: F) -> T
+    where
+        F: FnMut(&T) -> T,
+    {
+        if !T::CLONES_ARE_SAME {
+            let mut value = self.internal_lock();
+            let new = f(&value);
+            let copy = new.clone();
+            *value = new;
+            return copy;
+        }
+        let mut current = self.get();
+        loop {
+            let new = f(&current);
+            let copy = new.clone();
+            match self.compare_exchange_weak(&current, new) {
+                Ok(_) => return copy,
+                Err(_) => current = self.get(),
+            }
+        }
+    }
+}
+
+impl<T: AtomicCellStorable + Clone> Clone for AtomicCell<T> {
+    fn clone(&self) -> AtomicCell<T> {
+        AtomicCell::new(self.get())
+    }
+}
+
+impl<T: AtomicCellStorable + Default> AtomicCell<T> {
+    /// Take the value stored in the cell, replacing it with the default value.
+    pub fn take(&self) -> T {
+        // We must construct the new value first in case it panics.
+        let new_value = T::default();
+
+        let value = self.internal_take();
+        self.internal_put(new_value);
+
+        value
+    }
+
+    /// Replace the value in the cell with the default value, returning the old value.
+    ///
+    /// This has the same effect as [`take`](Self::take), but swaps the raw values directly
+    /// instead of taking the value out of the cell first.
+    pub fn set_default(&self) -> T {
+        let new_value = T::default().into_value();
+        let mut current = self.internal_load();
+        loop {
+            match self.value.compare_exchange_weak(
+                current,
+                new_value,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(old) => {
+                    self.notify();
+                    return unsafe { T::from_value(old) };
+                }
+                Err(_) => current = self.internal_load(),
+            }
+        }
+    }
+
+    /// Take the value stored in the cell if it satisfies `pred`, replacing it with the default.
+    ///
+    /// The value stays taken while `pred` runs, so no other thread can modify it in between.
+    pub fn take_if<F>(&self, pred: F) -> Option<T>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let new_value = T::default();
+
+        let mut value = self.internal_lock();
+        if pred(&value) {
+            Some(std::mem::replace(&mut *value, new_value))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: AtomicCellStorable + Default> Default for AtomicCell<T> {
+    fn default() -> Self {
+        AtomicCell::new(T::default())
+    }
+}
+
+impl<T: AtomicCellStorable + AtomicCellConstInit> AtomicCell<T> {
+    /// Create a new AtomicCell holding the default value, usable in statics.
+    ///
+    /// ```
+    /// use arc_cell::OptionalArcCell;
+    ///
+    /// static CONFIG: OptionalArcCell<String> = OptionalArcCell::const_new();
+    /// assert!(CONFIG.is_none());
+    /// ```
+    pub const fn const_new() -> Self {
+        AtomicCell {
+            value: AtomicUsize::new(T::DEFAULT_VALUE),
+            #[cfg(feature = "last-modified")]
+            modified: std::sync::atomic::AtomicU64::new(0),
+            #[cfg(feature = "stats")]
+            stats: stats::Counters::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Declares statics holding empty cells, initialized in a const context with `const_new`.
+///
+/// Next to each static this declares a module of the same name with typed accessors, so
+/// `CONFIG::get()`, `CONFIG::set(value)` and `CONFIG::swap(value)` work like the methods of the
+/// same names on the cell, except that `set` drops the old value and `swap` returns it. The
+/// accessors refer to the static through `super`, so the macro has to be used at module level
+/// rather than inside a function.
+///
+/// ```
+/// use arc_cell::{arc_cell_static, OptionalArcCell};
+/// use std::sync::Arc;
+///
+/// arc_cell_static! {
+///     /// The current configuration, if it was loaded.
+///     pub static CONFIG: OptionalArcCell<String>;
+/// }
+///
+/// fn main() {
+///     CONFIG::set(Some(Arc::new("loaded".to_string())));
+///     assert_eq!(CONFIG.get().as_deref().map(String::as_str), Some("loaded"));
+///     let old = CONFIG::swap(None);
+///     assert_eq!(old.as_deref().map(String::as_str), Some("loaded"));
+///     assert!(CONFIG::get().is_none());
+/// }
+/// ```
+#[macro_export]
+macro_rules! arc_cell_static {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $ty = <$ty>::const_new();
+
+            #[doc = concat!("Typed accessors for the [`", stringify!($name), "`] static.")]
+            // Not every static needs every accessor.
+            #[allow(non_snake_case, dead_code)]
+            $vis mod $name {
+                // The type of the static may name anything from the surrounding module.
+                #[allow(unused_imports)]
+                use super::*;
+
+                /// Returns a clone of the value in the static.
+                // The higher-ranked bound keeps this from failing to compile for values that
+                // can't be cloned, it is only checked when `get` is called.
+                pub fn get() -> <$ty as $crate::__private::CellValue>::Value
+                where
+                    for<'a> <$ty as $crate::__private::CellValue>::Value: Clone,
+                {
+                    super::$name.get()
+                }
+
+                /// Stores `value` in the static, dropping the old value.
+                pub fn set(value: <$ty as $crate::__private::CellValue>::Value) {
+                    super::$name.set(value);
+                }
+
+                /// Stores `value` in the static, returning the old value.
+                pub fn swap(
+                    value: <$ty as $crate::__private::CellValue>::Value,
+                ) -> <$ty as $crate::__private::CellValue>::Value {
+                    super::$name.set(value)
+                }
+            }
+        )*
+    };
+}
+
+impl<T> AtomicCell<Arc<T>> {
+    /// Create a new AtomicCell holding `value` in a new Arc.
+    pub fn from_value(value: T) -> Self {
+        AtomicCell::new(Arc::new(value))
+    }
+
+    /// Store `value` in a new Arc, returning the Arc that was replaced.
+    pub fn store_value(&self, value: T) -> Arc<T> {
+        self.set(Arc::new(value))
+    }
+
+    /// Consumes the cell, returning the inner value if the cell held the only strong reference.
+    ///
+    /// Otherwise the stored Arc is returned as the error, see [`Arc::try_unwrap`].
+    pub fn try_unwrap_inner(self) -> Result<T, Arc<T>> {
+        Arc::try_unwrap(self.into_inner())
+    }
+
+    /// Returns a guard dereferencing to the stored value.
+    ///
+    /// The cell is only taken for as long as it takes to clone the Arc, not for the lifetime of
+    /// the guard, so this is a shorthand for `&*cell.get()` that reads better in expressions.
+    pub fn read(&self) -> ReadGuard<'_, T> {
+        ReadGuard {
+            value: self.get(),
+            _cell: PhantomData,
+        }
+    }
+
+    /// Returns a raw pointer to the currently stored value, see [`Arc::as_ptr`].
+    ///
+    /// This does not take the value out of the cell or touch any reference counts, so the
+    /// pointer is only guaranteed to be valid for as long as the value stays in the cell.
+    pub fn as_ptr(&self) -> *const T {
+        self.internal_load() as *const T
+    }
+
+    /// Returns whether the cell currently points to the same allocation as `other`.
+    ///
+    /// This does not take the value out of the cell or touch any reference counts.
+    pub fn ptr_eq(&self, other: &Arc<T>) -> bool {
+        std::ptr::eq(self.as_ptr(), Arc::as_ptr(other))
+    }
+
+    /// Returns a Weak pointer to the stored value, without creating a temporary strong reference.
+    pub fn downgrade(&self) -> Weak<T> {
+        self.with(Arc::downgrade)
+    }
+
+    /// Returns a new cell holding a Weak pointer to the stored value.
+    pub fn downgrade_cell(&self) -> AtomicCell<Weak<T>> {
+        AtomicCell::new(self.downgrade())
+    }
+
+    /// Converts into an optional cell holding the same value.
+    pub fn into_optional(self) -> AtomicCell<Option<Arc<T>>> {
+        AtomicCell::new(Some(self.into_inner()))
+    }
+
+    /// Returns whether the stored Arc is the only reference to its value, strong or weak.
+    ///
+    /// This is the same check [`Arc::get_mut`] uses, and like the counts it is only a snapshot.
+    pub fn is_unique(&self) -> bool {
+        let mut value = self.internal_take();
+        let unique = Arc::get_mut(&mut value).is_some();
+        self.internal_restore(value);
+        unique
+    }
+
+    /// Mutates the stored value in place if the stored Arc is uniquely owned.
+    ///
+    /// See [`Arc::get_mut`]. Returns whether `f` was called. The value stays taken while `f`
+    /// runs, and is put back even if `f` panics.
+    pub fn try_update_unique<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut value = self.internal_lock();
+        Arc::get_mut(&mut value).map(f).is_some()
+    }
+
+    /// Returns a clone of the stored Arc if it has changed since `token` was last updated.
+    ///
+    /// When nothing has changed this only loads the raw pointer, without taking the value out
+    /// of the cell or touching any reference counts.
+    pub fn load_if_changed(&self, token: &mut ChangeToken<T>) -> Option<Arc<T>> {
+        if std::ptr::eq(self.as_ptr(), token.seen.as_ptr()) {
+            return None;
+        }
+
+        let value = self.get();
+        token.seen = Arc::downgrade(&value);
+        Some(value)
+    }
+
+    /// Stores `candidate` if it compares greater than the stored value, returning the previous
+    /// value either way.
+    ///
+    /// The value stays taken while `cmp` runs.
+    pub fn fetch_max_by<F>(&self, candidate: Arc<T>, cmp: F) -> Arc<T>
+    where
+        F: FnOnce(&T, &T) -> std::cmp::Ordering,
+    {
+        let mut value = self.internal_lock();
+        if cmp(&candidate, &value) == std::cmp::Ordering::Greater {
+            std::mem::replace(&mut *value, candidate)
+        } else {
+            Arc::clone(&value)
+        }
+    }
+
+    /// Stores `candidate` if it compares less than the stored value, returning the previous
+    /// value either way.
+    ///
+    /// The value stays taken while `cmp` runs.
+    pub fn fetch_min_by<F>(&self, candidate: Arc<T>, cmp: F) -> Arc<T>
+    where
+        F: FnOnce(&T, &T) -> std::cmp::Ordering,
+    {
+        self.fetch_max_by(candidate, |a, b| cmp(a, b).reverse())
+    }
+
+    /// Stores `new` into the cell if it currently points to `expected`, such as a pointer
+    /// previously returned by [`as_ptr`](Self::as_ptr).
+    ///
+    /// On success the previous Arc is returned, otherwise `new` is handed back to the caller.
+    /// Since the caller doesn't need to keep the expected value alive, its allocation may have
+    /// been freed and reused for a different value in the meantime, which would then be
+    /// replaced all the same. Use [`compare_exchange`](Self::compare_exchange) or a
+    /// [`GenArcCell`] if that matters.
+    pub fn replace_if(&self, expected: *const T, new: Arc<T>) -> Result<Arc<T>, Arc<T>> {
+        self.internal_compare_exchange(expected as usize, new)
+    }
+
+    /// Read-copy-update: builds a new value from the current one and stores it in a new Arc.
+    ///
+    /// `f` is retried until its result can be stored without a concurrent write intervening,
+    /// so it may be called more than once. Returns the Arc that was replaced.
+    pub fn rcu<F>(&self, mut f: F) -> Arc<T>
+    where
+        F: FnMut(&T) -> T,
+    {
+        match self.fetch_update(|current| Some(Arc::new(f(current)))) {
+            Ok(old) | Err(old) => old,
+        }
+    }
+
+    /// Atomically combines the stored value with `incoming` using `f`, storing the result in a
+    /// new Arc and returning the Arc that was replaced.
+    ///
+    /// `f` is called with the stored value first. Like [`rcu`](Self::rcu), it is retried if
+    /// another thread modifies the cell concurrently, so no merge is ever lost.
+    pub fn merge<F>(&self, incoming: Arc<T>, f: F) -> Arc<T>
+    where
+        F: Fn(&T, &T) -> T,
+    {
+        self.rcu(|current| f(current, &incoming))
+    }
+}
+
+impl<T: ?Sized> AtomicCell<Arc<T>> {
+    /// Returns the number of strong references to the stored value, see [`Arc::strong_count`].
+    ///
+    /// The reference held by the cell itself is included in the count.
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.internal_peek())
+    }
+
+    /// Returns the number of weak references to the stored value, see [`Arc::weak_count`].
+    pub fn weak_count(&self) -> usize {
+        Arc::weak_count(&self.internal_peek())
+    }
+}
+
+impl<T: 'static> AtomicCell<Arc<T>> {
+    /// Consumes the cell and leaks the stored Arc, returning a reference that lives forever.
+    pub fn leak(self) -> &'static T {
+        unsafe { &*Arc::into_raw(self.into_inner()) }
+    }
+
+    /// Leaks a clone of the stored Arc, returning a reference that lives forever.
+    ///
+    /// The cell itself can still be modified afterwards, but the value that was stored at the
+    /// time will never be freed.
+    pub fn leak_value(&self) -> &'static T {
+        unsafe { &*Arc::into_raw(self.get()) }
+    }
+}
+
+impl<T: PartialEq> AtomicCell<Arc<T>> {
+    /// Stores `new` into the cell if the stored value is equal to `expected`.
+    ///
+    /// Unlike [`compare_exchange`](Self::compare_exchange), this compares the pointed-to values
+    /// rather than the pointers. The value stays taken while it is compared. On success the
+    /// previous Arc is returned, otherwise `new` is handed back to the caller.
+    pub fn compare_exchange_eq(&self, expected: &T, new: Arc<T>) -> Result<Arc<T>, Arc<T>> {
+        let mut value = self.internal_lock();
+        if **value == *expected {
+            Ok(std::mem::replace(&mut *value, new))
+        } else {
+            Err(new)
+        }
+    }
+}
+
+impl<T: Clone> AtomicCell<Arc<T>> {
+    /// Returns a clone of the value the stored Arc points to.
+    pub fn clone_inner(&self) -> T {
+        self.with(|arc| T::clone(arc))
+    }
+
+    /// Mutates the stored value in place, cloning it first if the Arc is shared.
+    ///
+    /// See [`Arc::make_mut`]. The value stays taken while `f` runs, and is put back even if
+    /// `f` panics, so it may be left partially modified.
+    pub fn update_in_place<F>(&self, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        let mut value = self.internal_lock();
+        f(Arc::make_mut(&mut value));
+    }
+}
+
+impl<P> AtomicCell<Option<P>>
+where
+    Option<P>: AtomicCellStorable,
+{
+    /// Returns whether the cell currently holds a value.
+    ///
+    /// This only loads the raw value, without taking it or touching any reference counts.
+    pub fn is_some(&self) -> bool {
+        !self.is_none()
+    }
+
+    /// Returns whether the cell is currently empty.
+    ///
+    /// This only loads the raw value, without taking it or touching any reference counts.
+    pub fn is_none(&self) -> bool {
+        self.internal_load() == None::<P>.into_value()
+    }
+
+    /// Empty the cell, returning the value it held.
+    pub fn clear(&self) -> Option<P> {
+        self.set(None)
+    }
+
+    /// Take the value out of the cell if there is one, leaving it empty.
+    ///
+    /// Unlike [`clear`](Self::clear), this doesn't write to the cell at all if it is already
+    /// empty.
+    pub fn take_some(&self) -> Option<P> {
+        let empty = None::<P>.into_value();
+        let mut current = self.internal_load();
+        while current != empty {
+            match self.value.compare_exchange_weak(
+                current,
+                empty,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(old) => {
+                    self.notify();
+                    return unsafe { Option::<P>::from_value(old) };
+                }
+                Err(_) => current = self.internal_load(),
+            }
+        }
+        None
+    }
+
+    /// Store `value` in the cell only if it is currently empty.
+    ///
+    /// If the cell already holds a value, `value` is handed back as the error.
+    pub fn set_if_none(&self, value: P) -> Result<(), P> {
+        match self.compare_exchange(&None, Some(value)) {
+            Ok(_) => Ok(()),
+            // We get back exactly what we tried to store.
+            Err(value) => Err(value.unwrap()),
+        }
+    }
+
+    /// Returns the stored value, or stores and returns the result of `f` if the cell is empty.
+    ///
+    /// If multiple threads race to initialize the cell, each may call its own `f`, but only one
+    /// of the results will be stored and all of them will return that one.
+    pub fn get_or_insert_with<F>(&self, f: F) -> P
+    where
+        P: Clone,
+        F: FnOnce() -> P,
+    {
+        match self.get_or_try_init(|| Ok::<P, Infallible>(f())) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Returns the stored value, or stores and returns the result of `f` if the cell is empty.
+    ///
+    /// If `f` fails the cell is left empty and the error is returned, so initialization can be
+    /// retried later. As with [`get_or_insert_with`](Self::get_or_insert_with), `f` may be
+    /// called by multiple racing threads but only one result will be stored.
+    pub fn get_or_try_init<F, E>(&self, f: F) -> Result<P, E>
+    where
+        P: Clone,
+        F: FnOnce() -> Result<P, E>,
+    {
+        if let Some(value) = self.get() {
+            return Ok(value);
+        }
+
+        let value = f()?;
+        loop {
+            match self.set_if_none(value.clone()) {
+                Ok(()) => return Ok(value),
+                Err(_) => {
+                    // The cell could have been emptied again right after we lost the race.
+                    if let Some(existing) = self.get() {
+                        return Ok(existing);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> AtomicCell<Option<Arc<T>>> {
+    /// Create a new AtomicCell holding `value` in a new Arc.
+    pub fn from_value(value: T) -> Self {
+        AtomicCell::new(Some(Arc::new(value)))
+    }
+
+    /// Replaces the stored value with the result of `modify` if there is one, otherwise stores
+    /// the result of `insert`. Returns the newly stored value.
+    ///
+    /// The value stays taken while either closure runs, so no other thread can modify it in
+    /// between. If a closure panics the cell is left unchanged.
+    pub fn modify_or_insert<M, I>(&self, modify: M, insert: I) -> Arc<T>
+    where
+        M: FnOnce(&T) -> Arc<T>,
+        I: FnOnce() -> Arc<T>,
+    {
+        let mut value = self.internal_lock();
+        let new = match &*value {
+            Some(current) => modify(current),
+            None => insert(),
+        };
+        let old = value.replace(Arc::clone(&new));
+        // Release the cell before dropping the old value.
+        drop(value);
+        drop(old);
+        new
+    }
+
+    /// Returns a new cell holding a Weak pointer to the stored value, if any.
+    pub fn downgrade_cell(&self) -> AtomicCell<Option<Weak<T>>> {
+        AtomicCell::new(self.with(|value| value.as_ref().map(Arc::downgrade)))
+    }
+
+    /// Returns a raw pointer to the currently stored value, or null if the cell is empty.
+    ///
+    /// This does not take the value out of the cell or touch any reference counts, so the
+    /// pointer is only guaranteed to be valid for as long as the value stays in the cell.
+    pub fn as_ptr(&self) -> *const T {
+        self.internal_load() as *const T
+    }
+}
+
+impl AtomicCell<Option<Arc<dyn Any + Send + Sync>>> {
+    /// Returns the stored value if it is a `T`.
+    pub fn get_downcast<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.get()?.downcast().ok()
+    }
+
+    /// Replace the value in the cell with `value`, returning the old value.
+    pub fn set_any<T: Any + Send + Sync>(
+        &self,
+        value: Arc<T>,
+    ) -> Option<Arc<dyn Any + Send + Sync>> {
+        self.set(Some(value))
+    }
+
+    /// Returns whether the cell currently holds a `T`.
+    pub fn is<T: Any>(&self) -> bool {
+        self.with(|value| value.as_deref().is_some_and(|value| value.is::<T>()))
+    }
+}
+
+impl<T> AtomicCell<Weak<T>> {
+    /// Create a new AtomicCell with an empty Weak<T> stored inside.
+    pub fn empty() -> Self {
+        AtomicCell::new(Weak::new())
+    }
+
+    /// Returns a raw pointer to the value the stored Weak points to, see [`Weak::as_ptr`].
+    ///
+    /// This does not take the value out of the cell or touch any reference counts.
+    pub fn as_ptr(&self) -> *const T {
+        self.internal_load() as *const T
+    }
+}
+
+impl<T: ?Sized> AtomicCell<Weak<T>> {
+    /// Create a new AtomicCell holding a Weak pointer to `arc`.
+    pub fn from_arc(arc: &Arc<T>) -> Self {
+        AtomicCell::new(Arc::downgrade(arc))
+    }
+
+    /// Attempt to upgrade the Weak pointer to a strong Arc pointer.
+    pub fn upgrade(&self) -> Option<Arc<T>> {
+        self.get().upgrade()
+    }
+
+    /// Downgrade the Arc value and store it in the cell.
+    pub fn store(&self, arc: &Arc<T>) {
+        self.set(Arc::downgrade(arc));
+    }
+
+    /// Converts into an optional cell holding the same value.
+    pub fn into_optional(self) -> AtomicCell<Option<Weak<T>>> {
+        AtomicCell::new(Some(self.into_inner()))
+    }
+
+    /// Attempt to upgrade the Weak pointer, or store and return the result of `f` if it is dead.
+    ///
+    /// `f` is called at most once. If another thread stores a live value first, that value is
+    /// returned instead and the result of `f` is dropped.
+    pub fn upgrade_or_init<F>(&self, f: F) -> Arc<T>
+    where
+        F: FnOnce() -> Arc<T>,
+    {
+        let mut current = self.get();
+        if let Some(arc) = current.upgrade() {
+            return arc;
+        }
+
+        let arc = f();
+        let weak = Arc::downgrade(&arc);
+        loop {
+            match self.compare_exchange(&current, weak.clone()) {
+                Ok(_) => return arc,
+                Err(_) => {
+                    current = self.get();
+                    if let Some(existing) = current.upgrade() {
+                        return existing;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> AtomicCell<Option<Weak<T>>> {
+    /// Returns a raw pointer to the value the stored Weak points to, or null if the cell is
+    /// empty, see [`Weak::as_ptr`].
+    ///
+    /// This does not take the value out of the cell or touch any reference counts.
+    pub fn as_ptr(&self) -> *const T {
+        self.internal_load() as *const T
+    }
+}
+
+impl<T: ?Sized> AtomicCell<Option<Weak<T>>> {
+    /// Create a new AtomicCell holding a Weak pointer to `arc`.
+    pub fn from_arc(arc: &Arc<T>) -> Self {
+        AtomicCell::new(Some(Arc::downgrade(arc)))
+    }
+
+    /// Attempt to upgrade the Weak pointer to a strong Arc pointer (if it is not None).
+    pub fn upgrade(&self) -> Option<Arc<T>> {
+        self.get().and_then(|weak| weak.upgrade())
+    }
+
+    /// Downgrade the Arc value and store it in the cell.
+    pub fn store(&self, arc: &Arc<T>) {
+        self.set(Some(Arc::downgrade(arc)));
+    }
+
+    /// Empty the cell, attempting to upgrade the Weak pointer it held.
+    ///
+    /// Only one thread can receive the stored Weak pointer, so this returns `Some` at most once
+    /// for each value stored.
+    pub fn upgrade_and_clear(&self) -> Option<Arc<T>> {
+        self.take_some().and_then(|weak| weak.upgrade())
+    }
+
+    /// Empty the cell if the stored Weak pointer can no longer be upgraded.
+    ///
+    /// Returns whether a dead Weak pointer was removed.
+    pub fn prune(&self) -> bool {
+        let mut value = self.internal_lock();
+        if value.as_ref().is_some_and(|weak| weak.strong_count() == 0) {
+            let dead = value.take();
+            // Release the cell before freeing the allocation.
+            drop(value);
+            drop(dead);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<T: AtomicCellStorable> From<T> for AtomicCell<T> {
+    fn from(value: T) -> Self {
+        AtomicCell::new(value)
+    }
+}
+
+impl<T> From<AtomicCell<Arc<T>>> for AtomicCell<Option<Arc<T>>> {
+    fn from(cell: AtomicCell<Arc<T>>) -> Self {
+        cell.into_optional()
+    }
+}
+
+impl<T> From<AtomicCell<Weak<T>>> for AtomicCell<Option<Weak<T>>> {
+    fn from(cell: AtomicCell<Weak<T>>) -> Self {
+        cell.into_optional()
+    }
+}
+
+impl<T: AtomicCellStorable + Clone + Debug> Debug for AtomicCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("AtomicCell").field(&self.get()).finish()
+    }
+}
+
+/// Compares the stored values, e.g. the pointed-to data for [`ArcCell`].
+///
+/// Cells holding the same raw value (the same allocation for the pointer types) are considered
+/// equal without comparing any further, even if `T`'s `PartialEq` isn't reflexive.
+impl<T: AtomicCellStorable + Clone + PartialEq> PartialEq for AtomicCell<T> {
+    fn eq(&self, other: &AtomicCell<T>) -> bool {
+        self.same_value(other) || self.get() == other.get()
+    }
+}
+
+impl<T: AtomicCellStorable + Clone + Eq> Eq for AtomicCell<T> {}
+
+/// Hashes the stored value, consistent with the [`PartialEq`] impl.
+///
+/// As with any interior mutability, changing the value of a cell while it is used as a key in
+/// a hashed collection will make it unreachable.
+impl<T: AtomicCellStorable + Clone + Hash> Hash for AtomicCell<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state);
+    }
+}
+
+/// Compares by pointer identity, see [`ptr_eq`](AtomicCell::ptr_eq).
+///
+/// Unlike comparing two cells, this doesn't look at the pointed-to data: it answers whether
+/// `arc` is still the installed value, e.g. before a [`compare_exchange`](AtomicCell::compare_exchange).
+impl<T> PartialEq<Arc<T>> for AtomicCell<Arc<T>> {
+    fn eq(&self, arc: &Arc<T>) -> bool {
+        self.ptr_eq(arc)
+    }
+}
+
+impl<T: Display + ?Sized> Display for AtomicCell<Arc<T>> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        Display::fmt(&*self.get(), fmt)
+    }
+}
+
+/// Prints `<empty>` when the cell holds `None`.
+impl<T: Display + ?Sized> Display for AtomicCell<Option<Arc<T>>> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        match self.get() {
+            Some(value) => Display::fmt(&*value, fmt),
+            None => fmt.write_str("<empty>"),
+        }
+    }
+}
+
+/// A type that can be stored in an [`AtomicCell`] as a single `usize`.
+///
+/// # Safety
+///
+/// It is up to the implementer to ensure this is safe to implement.
+///
+/// `from_value` and `into_value` should never panic nor return TAKEN_VALUE.
+/// `into_value` must only transfer ownership and have no other side effects, so that
+/// two instances owning the same resource convert to the same raw value.
+/// If T implements Clone, cells put the value back when its clone() panics, so readers
+/// see the panic instead of the cell being left taken.
+pub unsafe trait AtomicCellStorable: Sized {
+    /// A sentinel value that a valid instance should never occupy.
+    const TAKEN_VALUE: usize;
+    /// Convert an instance into a raw value, transferring ownership.
+    fn into_value(self) -> usize;
+    /// Convert a raw value back into an instance.
+    ///
+    /// This consumes the raw value, and for indirect storables frees whatever it points to, so
+    /// it must never be called just to look at a value that stays in the cell. Convert the
+    /// instance back with `into_value` and store the new raw value instead.
+    ///
+    /// # Safety
+    ///
+    /// `value` must have been produced by `into_value`, and ownership of it is transferred
+    /// back into the returned instance.
+    unsafe fn from_value(value: usize) -> Self;
+
+    /// Whether `into_value` allocates a new raw value each time, so that equal instances don't
+    /// share a raw value and `as_value` isn't meaningful.
+    ///
+    /// Comparisons then take the value out of the cell and use [`same_as`](Self::same_as)
+    /// instead of comparing raw values. Types that set this must also implement
+    /// [`peek_value`](Self::peek_value).
+    const INDIRECT: bool = false;
+
+    /// Reads the instance a raw value stands for, leaving the raw value and whatever it points
+    /// to untouched, so the cell can read indirect values without allocating them anew.
+    ///
+    /// The returned instance is a shallow copy that must not be dropped. The default converts
+    /// the raw value with `from_value`, which is only right for types that aren't
+    /// [`INDIRECT`](Self::INDIRECT).
+    ///
+    /// # Safety
+    ///
+    /// `value` must have been produced by `into_value` and must outlive the returned instance.
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(Self::from_value(value))
+    }
+
+    /// Whether a clone of an instance is the same value as the original, as far as
+    /// [`same_as`](Self::same_as) is concerned.
+    ///
+    /// This holds for reference-counted pointers, whose clones point to the same allocation,
+    /// but not for owning pointers like `Box`, whose clones are new allocations. Methods that
+    /// clone the value, compute a new one from it and then swap it in with a compare, like
+    /// [`AtomicCell::fetch_update`], rely on this and would never succeed otherwise, so for
+    /// types that set it to `false` they compute the new value while the old one is taken.
+    const CLONES_ARE_SAME: bool = true;
+
+    /// Returns the raw value this instance would convert into, without transferring ownership.
+    fn as_value(&self) -> usize {
+        // The duplicate is consumed by `into_value`, so it is never dropped.
+        unsafe { std::ptr::read(self).into_value() }
+    }
+
+    /// Returns whether two instances are the same value, as far as
+    /// [`AtomicCell::compare_exchange`] is concerned.
+    fn same_as(&self, other: &Self) -> bool {
+        self.as_value() == other.as_value()
+    }
+}
+
+/// Whether pointers to `T` fit in a raw value. Pointers to unsized types also carry metadata,
+/// so those are boxed instead.
+const fn is_thin<T: ?Sized>() -> bool {
+    std::mem::size_of::<*const T>() == std::mem::size_of::<usize>()
+}
+
+#[cfg(not(feature = "allocator-api"))]
+fn arc_into_value<T: ?Sized>(arc: Arc<T>) -> usize {
+    if is_thin::<T>() {
+        let ptr = Arc::into_raw(arc);
+        unsafe { std::mem::transmute_copy::<*const T, usize>(&ptr) }
+    } else {
+        Box::into_raw(Box::new(arc)) as usize
+    }
+}
+
+#[cfg(not(feature = "allocator-api"))]
+unsafe fn arc_from_value<T: ?Sized>(value: usize) -> Arc<T> {
+    if is_thin::<T>() {
+        Arc::from_raw(std::mem::transmute_copy::<usize, *const T>(&value))
+    } else {
+        *Box::from_raw(value as *mut Arc<T>)
+    }
+}
+
+fn weak_into_value<T: ?Sized>(weak: Weak<T>) -> usize {
+    if is_thin::<T>() {
+        let ptr = Weak::into_raw(weak);
+        unsafe { std::mem::transmute_copy::<*const T, usize>(&ptr) }
+    } else {
+        Box::into_raw(Box::new(weak)) as usize
+    }
+}
+
+unsafe fn weak_from_value<T: ?Sized>(value: usize) -> Weak<T> {
+    if is_thin::<T>() {
+        Weak::from_raw(std::mem::transmute_copy::<usize, *const T>(&value))
+    } else {
+        *Box::from_raw(value as *mut Weak<T>)
+    }
+}
+
+// With `allocator-api` these are generalized over the allocator in `allocator_impl`.
+#[cfg(not(feature = "allocator-api"))]
+unsafe impl<T: ?Sized> AtomicCellStorable for Arc<T> {
+    const TAKEN_VALUE: usize = usize::MAX;
+    const INDIRECT: bool = !is_thin::<T>();
+
+    fn into_value(self) -> usize {
+        arc_into_value(self)
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        arc_from_value(value)
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        if is_thin::<T>() {
+            ManuallyDrop::new(arc_from_value(value))
+        } else {
+            // The box keeps its Arc, so this copy doesn't own a reference.
+            ManuallyDrop::new(std::ptr::read(value as *const Self))
+        }
+    }
+
+    fn as_value(&self) -> usize {
+        // Only thin pointers are their own raw value, this isn't used otherwise.
+        Arc::as_ptr(self) as *const () as usize
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        Arc::ptr_eq(self, other)
+    }
+}
+
+unsafe impl<T: ?Sized> AtomicCellStorable for Weak<T> {
+    // This must be MAX-1 because MAX is the sentinel value Weak uses for the empty state.
+    const TAKEN_VALUE: usize = usize::MAX - 1;
+    const INDIRECT: bool = !is_thin::<T>();
+
+    fn into_value(self) -> usize {
+        weak_into_value(self)
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        weak_from_value(value)
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        if is_thin::<T>() {
+            ManuallyDrop::new(weak_from_value(value))
+        } else {
+            // The box keeps its Weak, so this copy doesn't own a reference.
+            ManuallyDrop::new(std::ptr::read(value as *const Self))
+        }
+    }
+
+    fn as_value(&self) -> usize {
+        // Only thin pointers are their own raw value, this isn't used otherwise.
+        Weak::as_ptr(self) as *const () as usize
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        Weak::ptr_eq(self, other)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Box<T> {
+    const TAKEN_VALUE: usize = usize::MAX;
+    // Clones are new allocations.
+    const CLONES_ARE_SAME: bool = false;
+
+    fn into_value(self) -> usize {
+        Box::into_raw(self) as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        Box::from_raw(value as *mut T)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Pin<Box<T>> {
+    const TAKEN_VALUE: usize = <Box<T> as AtomicCellStorable>::TAKEN_VALUE;
+    // Clones are new allocations.
+    const CLONES_ARE_SAME: bool = false;
+
+    fn into_value(self) -> usize {
+        // The pointee is never moved while in the cell, only the Box pointer itself is.
+        unsafe { Box::into_raw(Pin::into_inner_unchecked(self)) as usize }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        Pin::new_unchecked(Box::from_raw(value as *mut T))
+    }
+}
+
+const EMPTY_OPTION: usize = 0;
+
+#[cfg(not(feature = "allocator-api"))]
+unsafe impl<T: ?Sized> AtomicCellStorable for Option<Arc<T>> {
+    const TAKEN_VALUE: usize = <Arc<T> as AtomicCellStorable>::TAKEN_VALUE;
+    const INDIRECT: bool = <Arc<T> as AtomicCellStorable>::INDIRECT;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(arc) => arc_into_value(arc),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(arc_from_value(value)),
+        }
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(match value {
+            EMPTY_OPTION => None,
+            value => Some(ManuallyDrop::into_inner(
+                <Arc<T> as AtomicCellStorable>::peek_value(value),
+            )),
+        })
+    }
+
+    fn as_value(&self) -> usize {
+        self.as_ref().map_or(EMPTY_OPTION, Arc::as_value)
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(arc), Some(other)) => Arc::ptr_eq(arc, other),
+            (arc, other) => arc.is_none() && other.is_none(),
+        }
+    }
+}
+
+unsafe impl<T: ?Sized> AtomicCellStorable for Option<Weak<T>> {
+    const TAKEN_VALUE: usize = <Weak<T> as AtomicCellStorable>::TAKEN_VALUE;
+    const INDIRECT: bool = <Weak<T> as AtomicCellStorable>::INDIRECT;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(weak) => weak_into_value(weak),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(weak_from_value(value)),
+        }
+    }
+
+    unsafe fn peek_value(value: usize) -> ManuallyDrop<Self> {
+        ManuallyDrop::new(match value {
+            EMPTY_OPTION => None,
+            value => Some(ManuallyDrop::into_inner(
+                <Weak<T> as AtomicCellStorable>::peek_value(value),
+            )),
+        })
+    }
+
+    fn as_value(&self) -> usize {
+        self.as_ref().map_or(EMPTY_OPTION, Weak::as_value)
+    }
+
+    fn same_as(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(weak), Some(other)) => Weak::ptr_eq(weak, other),
+            (weak, other) => weak.is_none() && other.is_none(),
+        }
+    }
+}
+
+/// A safe way to make a type storable in an [`AtomicCell`], for types that wrap an [`Arc`].
+///
+/// Implementing `PointerLike` and invoking [`impl_pointer_like!`] makes the type
+/// [`AtomicCellStorable`], with the cell holding just the Arc returned by `into_arc`. Anything
+/// else the type owns is dropped when it is stored, so `from_arc` should be able to rebuild it
+/// from the Arc alone.
+///
+/// `from_arc` must not panic, since it runs while the value is taken out of the cell and a panic
+/// there leaves the cell taken forever.
+///
+/// ```
+/// use arc_cell::{impl_pointer_like, AtomicCell, PointerLike};
+/// use std::sync::Arc;
+///
+/// #[derive(Clone)]
+/// struct Config(Arc<String>);
+///
+/// impl PointerLike for Config {
+///     type Target = String;
+///
+///     fn into_arc(self) -> Arc<String> {
+///         self.0
+///     }
+///     fn from_arc(arc: Arc<String>) -> Self {
+///         Config(arc)
+///     }
+///     fn as_arc(&self) -> &Arc<String> {
+///         &self.0
+///     }
+/// }
+///
+/// impl_pointer_like!(Config);
+///
+/// let cell = AtomicCell::new(Config(Arc::new("a".into())));
+/// assert_eq!(*cell.get().0, "a");
+/// ```
+pub trait PointerLike {
+    /// The type the Arc points to.
+    type Target;
+    /// Converts into the Arc to store in the cell.
+    fn into_arc(self) -> Arc<Self::Target>;
+    /// Converts back from an Arc that was stored in a cell.
+    fn from_arc(arc: Arc<Self::Target>) -> Self;
+    /// Returns the Arc that `into_arc` would return.
+    fn as_arc(&self) -> &Arc<Self::Target>;
+}
+
+/// Implements [`AtomicCellStorable`] for types implementing [`PointerLike`].
+///
+/// This is a macro rather than a blanket impl so that it doesn't stand in the way of the
+/// crate's own impls, e.g. for `Box<T>`. Generic types list their parameters up front:
+/// `impl_pointer_like!(impl<T> Wrapper<T>);`.
+#[macro_export]
+macro_rules! impl_pointer_like {
+    ($(impl<$($param:ident),* $(,)?>)? $ty:ty) => {
+        unsafe impl$(<$($param),*>)? $crate::AtomicCellStorable for $ty {
+            const TAKEN_VALUE: usize = $crate::__private::POINTER_LIKE_TAKEN_VALUE;
+
+            fn into_value(self) -> usize {
+                $crate::__private::pointer_like_into_value(self)
+            }
+
+            unsafe fn from_value(value: usize) -> Self {
+                $crate::__private::pointer_like_from_value(value)
+            }
+
+            fn as_value(&self) -> usize {
+                $crate::__private::pointer_like_as_value(self)
+            }
+        }
+    };
+}
+
+/// A reference-counted smart pointer that can be converted to and from a raw pointer, such as
+/// an intrusive Arc or one from another crate.
+///
+/// Implementing `RefCounted` and invoking [`impl_ref_counted!`] makes the pointer
+/// [`AtomicCellStorable`], with the cell holding the raw pointer and `get` cloning it.
+///
+/// # Safety
+///
+/// Implementations must uphold these invariants, which the cell relies on:
+///
+/// - `into_raw` returns a pointer that is never null and never `usize::MAX`, which holds for
+///   any pointer to a type with an alignment of 2 or more.
+/// - `from_raw` called with a pointer from `into_raw` gives back the same pointer, taking over
+///   the reference `into_raw` released. It is only ever called once per `into_raw`.
+/// - `as_ptr` returns the pointer that `into_raw` would return, without touching the count.
+/// - `clone` only bumps the count, so the pointee never moves while any reference exists.
+///
+/// ```
+/// use arc_cell::{impl_ref_counted, AtomicCell, RefCounted};
+/// use std::sync::Arc;
+///
+/// #[derive(Clone)]
+/// struct Shared(Arc<u32>);
+///
+/// unsafe impl RefCounted for Shared {
+///     type Target = u32;
+///
+///     fn into_raw(this: Self) -> *const u32 {
+///         Arc::into_raw(this.0)
+///     }
+///     unsafe fn from_raw(ptr: *const u32) -> Self {
+///         Shared(Arc::from_raw(ptr))
+///     }
+///     fn as_ptr(this: &Self) -> *const u32 {
+///         Arc::as_ptr(&this.0)
+///     }
+/// }
+///
+/// impl_ref_counted!(Shared);
+///
+/// let cell = AtomicCell::new(Shared(Arc::new(1)));
+/// assert_eq!(*cell.get().0, 1);
+/// ```
+pub unsafe trait RefCounted: Clone {
+    /// The type the pointer points to.
+    type Target;
+    /// Releases the pointer, returning the raw pointer that owns its reference.
+    fn into_raw(this: Self) -> *const Self::Target;
+    /// Rebuilds the pointer from a raw pointer returned by `into_raw`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have come from `into_raw` and not been passed to `from_raw` since.
+    unsafe fn from_raw(ptr: *const Self::Target) -> Self;
+    /// Returns the raw pointer without releasing the reference.
+    fn as_ptr(this: &Self) -> *const Self::Target;
+}
+
+unsafe impl<T> RefCounted for Arc<T> {
+    type Target = T;
+
+    fn into_raw(this: Self) -> *const T {
+        Arc::into_raw(this)
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Arc::from_raw(ptr)
+    }
+
+    fn as_ptr(this: &Self) -> *const T {
+        Arc::as_ptr(this)
+    }
+}
+
+/// Implements [`AtomicCellStorable`] for types implementing [`RefCounted`].
+///
+/// Like [`impl_pointer_like!`] this is a macro rather than a blanket impl, and generic types
+/// list their parameters up front: `impl_ref_counted!(impl<T> MyArc<T>);`.
+#[macro_export]
+macro_rules! impl_ref_counted {
+    ($(impl<$($param:ident),* $(,)?>)? $ty:ty) => {
+        unsafe impl$(<$($param),*>)? $crate::AtomicCellStorable for $ty {
+            const TAKEN_VALUE: usize = usize::MAX;
+
+            fn into_value(self) -> usize {
+                <$ty as $crate::RefCounted>::into_raw(self) as usize
+            }
+
+            unsafe fn from_value(value: usize) -> Self {
+                <$ty as $crate::RefCounted>::from_raw(value as *const _)
+            }
+
+            fn as_value(&self) -> usize {
+                <$ty as $crate::RefCounted>::as_ptr(self) as usize
+            }
+        }
+    };
+}
+
+/// Defines a fieldless enum that can be stored in an [`AtomicCell`], such as a mode or state.
+///
+/// Each variant is stored as its position in the enum rather than its discriminant, so
+/// variants can have any explicit discriminants. Cells created with `const_new` start out
+/// holding the first variant. With the `derive` feature, `#[derive(AtomicCellStorable)]`
+/// does the same for enums defined without this macro.
+///
+/// ```
+/// use arc_cell::{storable_enum, AtomicCell};
+///
+/// storable_enum! {
+///     #[derive(Clone, Copy, Debug, PartialEq)]
+///     pub enum Mode {
+///         Normal,
+///         Maintenance = 10,
+///     }
+/// }
+///
+/// static MODE: AtomicCell<Mode> = AtomicCell::const_new();
+/// assert_eq!(MODE.set(Mode::Maintenance), Mode::Normal);
+/// assert_eq!(MODE.get(), Mode::Maintenance);
+/// ```
+#[macro_export]
+macro_rules! storable_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_attr:meta])* $variant:ident $(= $discriminant:expr)?),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name {
+            $($(#[$variant_attr])* $variant $(= $discriminant)?,)+
+        }
+
+        $crate::__storable_enum_impl!($name { $($variant),+ });
+    };
+}
+
+/// Implements the storable traits for a fieldless enum, see [`storable_enum!`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __storable_enum_impl {
+    ($name:ident { $($variant:ident),+ }) => {
+        unsafe impl $crate::AtomicCellStorable for $name {
+            // Positions are far smaller than this, whatever the discriminants are.
+            const TAKEN_VALUE: usize = usize::MAX;
+
+            #[allow(unused_assignments)]
+            fn into_value(self) -> usize {
+                let discriminant = self as isize;
+                let mut index = 0;
+                $(
+                    if discriminant == $name::$variant as isize {
+                        return index;
+                    }
+                    index += 1;
+                )+
+                unreachable!("every variant of {} is listed", stringify!($name))
+            }
+
+            #[allow(unused_assignments)]
+            unsafe fn from_value(value: usize) -> Self {
+                let mut index = 0;
+                $(
+                    if value == index {
+                        return $name::$variant;
+                    }
+                    index += 1;
+                )+
+                unreachable!("invalid raw value {} for {}", value, stringify!($name))
+            }
+        }
+
+        unsafe impl $crate::AtomicCellConstInit for $name {
+            const DEFAULT_VALUE: usize = 0;
+        }
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use super::{AtomicCell, AtomicCellStorable, PointerLike};
+    use std::sync::Arc;
+
+    /// The type stored in a cell, for the accessors declared by `arc_cell_static!`.
+    pub trait CellValue {
+        type Value;
+    }
+
+    impl<T: AtomicCellStorable> CellValue for AtomicCell<T> {
+        type Value = T;
+    }
+
+    pub const POINTER_LIKE_TAKEN_VALUE: usize = <Arc<()> as AtomicCellStorable>::TAKEN_VALUE;
+
+    pub fn pointer_like_into_value<P: PointerLike>(value: P) -> usize {
+        value.into_arc().into_value()
+    }
+
+    /// # Safety
+    ///
+    /// `value` must have been produced by `pointer_like_into_value::<P>`.
+    pub unsafe fn pointer_like_from_value<P: PointerLike>(value: usize) -> P {
+        P::from_arc(Arc::from_value(value))
+    }
+
+    pub fn pointer_like_as_value<P: PointerLike>(value: &P) -> usize {
+        Arc::as_ptr(value.as_arc()) as usize
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<Box<T>> {
+    const TAKEN_VALUE: usize = <Box<T> as AtomicCellStorable>::TAKEN_VALUE;
+    // Clones are new allocations.
+    const CLONES_ARE_SAME: bool = false;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(boxed) => Box::into_raw(boxed) as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(Box::from_raw(value as *mut T)),
+        }
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<Pin<Box<T>>> {
+    const TAKEN_VALUE: usize = <Box<T> as AtomicCellStorable>::TAKEN_VALUE;
+    // Clones are new allocations.
+    const CLONES_ARE_SAME: bool = false;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(pinned) => pinned.into_value(),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(Pin::from_value(value)),
+        }
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for &'static T {
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        // Any aligned address is a valid reference to a zero-sized value, including the taken
+        // value, so they are all stored as the same dangling address.
+        if std::mem::size_of::<T>() == 0 {
+            std::mem::align_of::<T>()
+        } else {
+            self as *const T as usize
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        &*(value as *const T)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<&'static T> {
+    const TAKEN_VALUE: usize = <&'static T as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(value) => value.into_value(),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(<&'static T>::from_value(value)),
+        }
+    }
+}
+
+/// A small error type that can be packed next to an Arc, see [`ResultCell`].
+///
+/// `from_code` is only ever called with codes returned by `to_code`, and it should not panic:
+/// a panic leaves the cell taken forever, so reads and writes of it will hang.
+pub trait ErrorCode {
+    /// Returns the code for the error.
+    fn to_code(&self) -> u16;
+    /// Converts a code back into the error.
+    fn from_code(code: u16) -> Self;
+}
+
+impl ErrorCode for u8 {
+    fn to_code(&self) -> u16 {
+        (*self).into()
+    }
+
+    fn from_code(code: u16) -> Self {
+        code as u8
+    }
+}
+
+impl ErrorCode for u16 {
+    fn to_code(&self) -> u16 {
+        *self
+    }
+
+    fn from_code(code: u16) -> Self {
+        code
+    }
+}
+
+/// Set in the raw value of an `Err`. Arc pointers are always aligned to at least a `usize`,
+/// so this bit is otherwise never set.
+const ERROR_TAG: usize = 1;
+
+unsafe impl<T, E: ErrorCode> AtomicCellStorable for Result<Arc<T>, E> {
+    // Codes are only 16 bits, so this can never be an error code either.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        match self {
+            Ok(arc) => arc.into_value(),
+            Err(error) => (usize::from(error.to_code()) << 1) | ERROR_TAG,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        if value & ERROR_TAG == 0 {
+            Ok(Arc::from_value(value))
+        } else {
+            Err(E::from_code((value >> 1) as u16))
+        }
+    }
+
+    // The default impl would duplicate the error, which may own resources.
+    fn as_value(&self) -> usize {
+        match self {
+            Ok(arc) => Arc::as_ptr(arc) as usize,
+            Err(error) => (usize::from(error.to_code()) << 1) | ERROR_TAG,
+        }
+    }
+}
+
+macro_rules! fn_storable {
+    ($($arg:ident),*) => {
+        unsafe impl<R, $($arg),*> AtomicCellStorable for fn($($arg),*) -> R {
+            // Function pointers are never null and never point to the very last byte of the
+            // address space.
+            const TAKEN_VALUE: usize = usize::MAX;
+
+            fn into_value(self) -> usize {
+                self as usize
+            }
+
+            unsafe fn from_value(value: usize) -> Self {
+                std::mem::transmute::<usize, Self>(value)
+            }
+        }
+
+        unsafe impl<R, $($arg),*> AtomicCellStorable for Option<fn($($arg),*) -> R> {
+            const TAKEN_VALUE: usize = usize::MAX;
+
+            fn into_value(self) -> usize {
+                match self {
+                    None => EMPTY_OPTION,
+                    Some(f) => f.into_value(),
+                }
+            }
+
+            unsafe fn from_value(value: usize) -> Self {
+                match value {
+                    EMPTY_OPTION => None,
+                    value => Some(<fn($($arg),*) -> R>::from_value(value)),
+                }
+            }
+        }
+
+        unsafe impl<R, $($arg),*> AtomicCellConstInit for Option<fn($($arg),*) -> R> {
+            const DEFAULT_VALUE: usize = EMPTY_OPTION;
+        }
+    };
+}
+
+fn_storable!();
+fn_storable!(A1);
+fn_storable!(A1, A2);
+fn_storable!(A1, A2, A3);
+fn_storable!(A1, A2, A3, A4);
+fn_storable!(A1, A2, A3, A4, A5);
+fn_storable!(A1, A2, A3, A4, A5, A6);
+
+/// Storable types which have a raw value usable to initialize a cell in a const context.
+///
+/// # Safety
+///
+/// `DEFAULT_VALUE` must be a raw value that is valid to pass to `from_value`.
+pub unsafe trait AtomicCellConstInit {
+    /// The raw value of the default instance.
+    const DEFAULT_VALUE: usize;
+}
+
+#[cfg(not(feature = "allocator-api"))]
+unsafe impl<T: ?Sized> AtomicCellConstInit for Option<Arc<T>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+unsafe impl<T: ?Sized> AtomicCellConstInit for Option<Weak<T>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<Box<T>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<Pin<Box<T>>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<&'static T> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        snapshot2, AnyArcCell, ArcCell, AtomicCell, BoxCell, ChangeToken, ErrorCode, FnCell,
+        OptionalArcCell, OptionalBoxCell, OptionalWeakCell, Pin, ReadGuard, RefCounted, ResultCell,
+        StaticCell, TimeoutError, WeakCell,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    #[test]
+    fn arc_cell() {
+        let data1 = Arc::new(5);
+        let data2 = Arc::new(6);
+
+        let cell = ArcCell::new(data1);
+        assert_eq!(*cell.get(), 5);
+        cell.set(data2);
+        assert_eq!(*cell.get(), 6);
+    }
+
+    #[test]
+    fn weak_cell() {
+        let data = Arc::new(5);
+
+        let cell = WeakCell::empty();
+        cell.store(&data);
+        assert_eq!(cell.upgrade(), Some(data.clone()));
+        drop(data);
+        assert_eq!(cell.upgrade(), None);
+    }
+
+    #[test]
+    fn compare_exchange() {
+        let data1 = Arc::new(5);
+        let data2 = Arc::new(6);
+
+        let cell = ArcCell::new(data1.clone());
+        assert_eq!(
+            cell.compare_exchange(&data2, Arc::new(7)).map_err(|v| *v),
+            Err(7)
+        );
+        assert!(Arc::ptr_eq(
+            &cell.compare_exchange(&data1, data2).unwrap(),
+            &data1
+        ));
+        assert_eq!(*cell.get(), 6);
+    }
+
+    #[test]
+    fn compare_exchange_weak() {
+        let data1 = Arc::new(5);
+        let data2 = Arc::new(6);
+
+        let cell = ArcCell::new(data1.clone());
+        assert_eq!(
+            cell.compare_exchange_weak(&data2, Arc::new(7))
+                .map_err(|v| *v),
+            Err(7)
+        );
+        assert_eq!(*cell.get(), 5);
+        // Success may fail spuriously, so retry with the value handed back.
+        let mut new = data2.clone();
+        let old = loop {
+            match cell.compare_exchange_weak(&data1, new) {
+                Ok(old) => break old,
+                Err(back) => new = back,
+            }
+        };
+        assert!(Arc::ptr_eq(&old, &data1));
+        assert!(Arc::ptr_eq(&cell.get(), &data2));
+
+        let counter = Arc::new(ArcCell::new(Arc::new(0)));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let mut current = counter.get();
+                        while counter
+                            .compare_exchange_weak(&current, Arc::new(*current + 1))
+                            .is_err()
+                        {
+                            current = counter.get();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(*counter.get(), 4000);
+    }
+
+    #[test]
+    fn fetch_update() {
+        let cell = Arc::new(ArcCell::new(Arc::new(0)));
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        cell.fetch_update(|v| Some(Arc::new(**v + 1))).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(cell.fetch_update(|_| None).map_err(|v| *v), Err(4000));
+    }
+
+    #[test]
+    fn rcu() {
+        let cell = ArcCell::new(Arc::new(vec![1, 2]));
+        let old = cell.rcu(|v| {
+            let mut v = v.clone();
+            v.push(3);
+            v
+        });
+        assert_eq!(*old, [1, 2]);
+        assert_eq!(*cell.get(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn update_and_get() {
+        let cell = ArcCell::new(Arc::new(1));
+        assert_eq!(*cell.get_and_update(|v| Arc::new(**v * 10)), 1);
+        assert_eq!(*cell.update_and_get(|v| Arc::new(**v + 1)), 11);
+        assert_eq!(*cell.get(), 11);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut cell = ArcCell::new(Arc::new(1));
+        *cell.get_mut() = Arc::new(2);
+        *Arc::make_mut(&mut cell.get_mut()) += 1;
+        assert_eq!(*cell.get(), 3);
+    }
+
+    #[test]
+    fn get_mut_leaked() {
+        let mut cell = ArcCell::new(Arc::new(5));
+        std::mem::forget(cell.get_mut());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| **cell.get_mut()));
+        assert!(result.is_err());
+        assert!(cell.is_contended());
+    }
+
+    #[test]
+    fn into_inner() {
+        let data = Arc::new(5);
+        let cell = ArcCell::new(data.clone());
+        assert_eq!(Arc::strong_count(&data), 2);
+        assert!(Arc::ptr_eq(&cell.into_inner(), &data));
+        assert_eq!(Arc::strong_count(&data), 1);
+    }
+
+    #[test]
+    fn try_unwrap_inner() {
+        let data = Arc::new(5);
+        let cell = ArcCell::new(data.clone());
+        let cell = ArcCell::new(cell.try_unwrap_inner().unwrap_err());
+        drop(data);
+        assert_eq!(cell.try_unwrap_inner(), Ok(5));
+    }
+
+    #[test]
+    fn ptr_eq() {
+        let data = Arc::new(5);
+        let cell = ArcCell::new(data.clone());
+        assert!(cell.ptr_eq(&data));
+        assert!(!cell.ptr_eq(&Arc::new(5)));
+
+        let ptr = cell.as_ptr();
+        assert_eq!(cell.replace_if(ptr, Arc::new(6)), Ok(Arc::new(5)));
+        assert_eq!(cell.replace_if(ptr, Arc::new(7)), Err(Arc::new(7)));
+    }
+
+    #[test]
+    fn same_value() {
+        let data = Arc::new(5);
+        let cell1 = ArcCell::new(data.clone());
+        let cell2 = ArcCell::new(data);
+        assert!(cell1.same_value(&cell2));
+        assert_eq!(*cell2.store_value(5), 5);
+        assert!(!cell1.same_value(&cell2));
+    }
+
+    #[test]
+    fn same_value_unsized() {
+        let name: Arc<str> = Arc::from("a");
+        let cell1: ArcCell<str> = ArcCell::new(name.clone());
+        let cell2: ArcCell<str> = ArcCell::new(name);
+        assert!(cell1.same_value(&cell2));
+        assert!(cell2.same_value(&cell1));
+        cell2.set(Arc::from("a"));
+        assert!(!cell1.same_value(&cell2));
+        assert!(cell1 == cell2);
+    }
+
+    #[test]
+    fn as_ptr() {
+        let data = Arc::new(5);
+        assert_eq!(ArcCell::new(data.clone()).as_ptr(), Arc::as_ptr(&data));
+        assert_eq!(
+            WeakCell::new(Arc::downgrade(&data)).as_ptr(),
+            Arc::as_ptr(&data)
+        );
+        assert!(OptionalArcCell::<i32>::new(None).as_ptr().is_null());
+    }
+
+    #[test]
+    fn counts() {
+        let data = Arc::new(5);
+        let weak = Arc::downgrade(&data);
+        let cell = ArcCell::new(data.clone());
+        assert_eq!(cell.strong_count(), 2);
+        assert_eq!(cell.weak_count(), 1);
+        drop(data);
+        assert!(!cell.is_unique());
+        assert!(!cell.try_update_unique(|v| *v += 1));
+        drop(weak);
+        assert_eq!(cell.strong_count(), 1);
+        assert_eq!(cell.weak_count(), 0);
+        assert!(cell.is_unique());
+        assert!(cell.try_update_unique(|v| *v += 1));
+        assert_eq!(*cell.get(), 6);
+    }
+
+    #[test]
+    fn with() {
+        let data = Arc::new(vec![1, 2, 3]);
+        let cell = ArcCell::new(data.clone());
+        assert_eq!(cell.with(|v| v.len()), 3);
+        assert_eq!(Arc::strong_count(&data), 2);
+
+        let result = std::panic::catch_unwind(|| cell.with(|_| panic!()));
+        assert!(result.is_err());
+        assert!(cell.ptr_eq(&data));
+    }
+
+    #[test]
+    fn get_if() {
+        let cell = ArcCell::new(Arc::new(5));
+        assert_eq!(cell.get_if(|v| **v > 3), Some(Arc::new(5)));
+        assert_eq!(cell.get_if(|v| **v > 5), None);
+    }
 
     #[test]
-    fn arc_cell() {
-        let data1 = Arc::new(5);
-        let data2 = Arc::new(6);
+    fn clone_inner() {
+        let data = Arc::new(vec![1, 2]);
+        let cell = ArcCell::new(data.clone());
+        assert_eq!(cell.clone_inner(), [1, 2]);
+        assert_eq!(Arc::strong_count(&data), 2);
+    }
 
-        let cell = ArcCell::new(data1);
-        assert_eq!(*cell.get(), 5);
-        cell.set(data2);
-        assert_eq!(*cell.get(), 6);
+    #[test]
+    fn from_value() {
+        assert_eq!(*ArcCell::from_value(5).get(), 5);
+        assert_eq!(OptionalArcCell::from_value(5).get().as_deref(), Some(&5));
+
+        let data = Arc::new(5);
+        assert_eq!(WeakCell::from_arc(&data).upgrade(), Some(data.clone()));
+        assert_eq!(ArcCell::new(data.clone()).downgrade().upgrade(), Some(data));
     }
 
     #[test]
-    fn weak_cell() {
+    fn replace_with() {
+        let cell = ArcCell::new(Arc::new(vec![1]));
+        let old = cell.replace_with(|old| {
+            let mut items = std::mem::take(Arc::get_mut(old).unwrap());
+            items.push(2);
+            Arc::new(items)
+        });
+        assert!(old.is_empty());
+        assert_eq!(*cell.get(), [1, 2]);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            cell.replace_with(|old| {
+                Arc::make_mut(old).push(3);
+                panic!("no new value")
+            })
+        }));
+        assert!(result.is_err());
+        assert!(!cell.is_contended());
+        assert_eq!(*cell.get(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn update_in_place() {
+        let data = Arc::new(vec![1]);
+        let cell = ArcCell::new(data.clone());
+        cell.update_in_place(|v| v.push(2));
+        assert_eq!(*data, [1]);
+        drop(data);
+
+        let ptr = cell.as_ptr();
+        cell.update_in_place(|v| v.push(3));
+        assert_eq!(cell.as_ptr(), ptr);
+        assert_eq!(*cell.get(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn set_default() {
+        let cell = OptionalArcCell::from_value(5);
+        assert_eq!(cell.set_default(), Some(Arc::new(5)));
+        assert_eq!(cell.get(), None);
+        assert_eq!(cell.set_default(), None);
+        assert_eq!(cell.get(), None);
+
+        let boxed = BoxCell::new(Box::new(vec![1, 2]));
+        assert_eq!(*boxed.set_default(), [1, 2]);
+        assert!(boxed.get().is_empty());
+    }
+
+    #[test]
+    fn take_if() {
+        let cell = OptionalArcCell::from_value(5);
+        assert_eq!(cell.take_if(|v| v.as_deref() == Some(&6)), None);
+        assert_eq!(
+            cell.take_if(|v| v.as_deref() == Some(&5)),
+            Some(Some(Arc::new(5)))
+        );
+        assert_eq!(cell.get(), None);
+    }
+
+    #[test]
+    fn swap_with() {
+        let cell1 = Arc::new(ArcCell::from_value(1));
+        let cell2 = Arc::new(ArcCell::from_value(2));
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let (a, b) = if i % 2 == 0 {
+                    (cell1.clone(), cell2.clone())
+                } else {
+                    (cell2.clone(), cell1.clone())
+                };
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        a.swap_with(&b);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!((*cell1.get(), *cell2.get()), (1, 2));
+
+        cell1.swap_with(&cell1);
+        cell1.swap_with(&cell2);
+        assert_eq!((*cell1.get(), *cell2.get()), (2, 1));
+    }
+
+    #[test]
+    fn try_get_set() {
+        let cell = ArcCell::from_value(5);
+        assert_eq!(cell.try_get(), Some(Arc::new(5)));
+        assert!(!cell.is_contended());
+        cell.with(|_| assert!(cell.is_contended()));
+        cell.with(|_| assert_eq!(cell.try_get(), None));
+        cell.with(|_| assert_eq!(cell.try_set(Arc::new(6)), Err(Arc::new(6))));
+        assert_eq!(cell.try_set(Arc::new(6)), Ok(Arc::new(5)));
+    }
+
+    #[test]
+    fn timeouts() {
+        let cell = ArcCell::from_value(5);
+        let timeout = Duration::from_millis(10);
+        assert_eq!(cell.get_timeout(timeout), Ok(Arc::new(5)));
+        cell.with(|_| {
+            assert_eq!(cell.get_timeout(timeout), Err(TimeoutError(())));
+            assert_eq!(
+                cell.set_timeout(Arc::new(6), timeout),
+                Err(TimeoutError(Arc::new(6)))
+            );
+        });
+        assert_eq!(cell.set_timeout(Arc::new(6), timeout), Ok(Arc::new(5)));
+    }
+
+    #[test]
+    fn set_if_none() {
+        let cell = OptionalArcCell::new(None);
+        assert!(cell.is_none());
+        assert_eq!(cell.set_if_none(Arc::new(5)), Ok(()));
+        assert!(cell.is_some());
+        assert_eq!(cell.set_if_none(Arc::new(6)), Err(Arc::new(6)));
+        assert_eq!(cell.clear(), Some(Arc::new(5)));
+        assert_eq!(cell.clear(), None);
+        cell.set(Some(Arc::new(6)));
+        assert_eq!(cell.take_some(), Some(Arc::new(6)));
+        assert_eq!(cell.take_some(), None);
+    }
+
+    #[test]
+    fn get_or_insert_with() {
+        let cell = Arc::new(OptionalArcCell::new(None));
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let cell = cell.clone();
+                std::thread::spawn(move || cell.get_or_insert_with(|| Arc::new(i)))
+            })
+            .collect();
+        let values: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(values.iter().all(|v| cell.as_ptr() == Arc::as_ptr(v)));
+    }
+
+    #[test]
+    fn get_or_try_init() {
+        let cell = OptionalArcCell::new(None);
+        assert_eq!(cell.get_or_try_init(|| Err("failed")), Err("failed"));
+        assert_eq!(cell.get(), None);
+        assert_eq!(
+            cell.get_or_try_init(|| Ok::<_, ()>(Arc::new(5))),
+            Ok(Arc::new(5))
+        );
+        assert_eq!(cell.get_or_try_init(|| Err("failed")), Ok(Arc::new(5)));
+    }
+
+    #[test]
+    fn prune() {
         let data = Arc::new(5);
+        let cell = OptionalWeakCell::from_arc(&data);
+        assert!(!cell.prune());
+        assert_eq!(cell.upgrade_and_clear(), Some(data.clone()));
+        assert_eq!(cell.upgrade_and_clear(), None);
+        cell.store(&data);
+        drop(data);
+        assert!(cell.is_some());
+        assert!(cell.prune());
+        assert!(cell.is_none());
+        assert!(!cell.prune());
+    }
 
+    #[test]
+    fn upgrade_or_init() {
         let cell = WeakCell::empty();
-        cell.store(&data);
-        assert_eq!(cell.upgrade(), Some(data.clone()));
+        let data = cell.upgrade_or_init(|| Arc::new(5));
+        assert!(Arc::ptr_eq(&cell.upgrade_or_init(|| Arc::new(6)), &data));
         drop(data);
-        assert_eq!(cell.upgrade(), None);
+        assert_eq!(*cell.upgrade_or_init(|| Arc::new(6)), 6);
+    }
+
+    #[test]
+    fn conversions() {
+        let data = Arc::new(5);
+        let cell = ArcCell::new(data.clone());
+        let weak: WeakCell<i32> = cell.downgrade_cell();
+        assert_eq!(weak.as_ptr(), Arc::as_ptr(&data));
+
+        let optional: OptionalArcCell<i32> = cell.into();
+        assert_eq!(optional.downgrade_cell().upgrade(), Some(data.clone()));
+        let weak: OptionalWeakCell<i32> = weak.into();
+        assert_eq!(weak.upgrade(), Some(data));
+    }
+
+    #[test]
+    fn from_impls() {
+        let cell = ArcCell::from_value(5);
+        assert_eq!(*cell.get(), 5);
+        let cell: ArcCell<i32> = Arc::new(5).into();
+        assert_eq!(*cell.get(), 5);
+        // Only one impl converts from an Arc, so the cell type can be inferred.
+        let cell = AtomicCell::from(Arc::new(5));
+        assert_eq!(*cell.get(), 5);
+        let cell: OptionalArcCell<i32> = Some(Arc::new(5)).into();
+        assert_eq!(cell.get(), Some(Arc::new(5)));
+    }
+
+    #[test]
+    fn load_if_changed() {
+        let cell = ArcCell::from_value(5);
+        let mut token = ChangeToken::new();
+        assert_eq!(cell.load_if_changed(&mut token), Some(Arc::new(5)));
+        assert_eq!(cell.load_if_changed(&mut token), None);
+        cell.store_value(6);
+        assert_eq!(cell.load_if_changed(&mut token), Some(Arc::new(6)));
+        assert_eq!(cell.load_if_changed(&mut token), None);
+    }
+
+    #[test]
+    fn wait_until() {
+        let cell = Arc::new(ArcCell::from_value(0));
+        let waiter = {
+            let cell = cell.clone();
+            std::thread::spawn(move || *cell.wait_until(|v| **v == 3))
+        };
+        for i in 1..=3 {
+            std::thread::sleep(Duration::from_millis(10));
+            cell.store_value(i);
+        }
+        assert_eq!(waiter.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn compare_exchange_eq() {
+        let cell = ArcCell::from_value(5);
+        assert_eq!(cell.compare_exchange_eq(&6, Arc::new(7)), Err(Arc::new(7)));
+        assert_eq!(cell.compare_exchange_eq(&5, Arc::new(7)), Ok(Arc::new(5)));
+        assert_eq!(*cell.get(), 7);
+    }
+
+    #[test]
+    fn raw() {
+        let data = Arc::new(5);
+        let raw = ArcCell::new(data.clone()).into_raw();
+        assert_eq!(raw, Arc::as_ptr(&data) as usize);
+        let cell = unsafe { ArcCell::<i32>::from_raw(raw) };
+        assert_eq!(unsafe { cell.as_atomic() }.load(Ordering::SeqCst), raw);
+        assert!(cell.ptr_eq(&data));
+    }
+
+    #[test]
+    fn leak() {
+        let cell = ArcCell::from_value(5);
+        let value = cell.leak_value();
+        cell.store_value(6);
+        assert_eq!((*value, *cell.leak()), (5, 6));
+    }
+
+    #[test]
+    fn orderings() {
+        let cell = ArcCell::from_value(5);
+        assert_eq!(*cell.get_with(Ordering::Acquire), 5);
+        assert_eq!(
+            *cell.set_with(Arc::new(6), Ordering::Acquire, Ordering::Release),
+            5
+        );
+        assert_eq!(*cell.get_with(Ordering::SeqCst), 6);
+        assert!(std::panic::catch_unwind(|| cell.get_with(Ordering::Relaxed)).is_err());
+        assert_eq!(*cell.get(), 6);
+    }
+
+    #[test]
+    fn override_scoped() {
+        let cell = ArcCell::from_value(1);
+        {
+            let _guard = cell.override_scoped(Arc::new(2));
+            assert_eq!(*cell.get(), 2);
+        }
+        assert_eq!(*cell.get(), 1);
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = cell.override_scoped(Arc::new(3));
+            panic!();
+        });
+        assert!(result.is_err());
+        assert_eq!(*cell.get(), 1);
+    }
+
+    #[test]
+    fn fetch_max_by() {
+        let cell = ArcCell::from_value(5);
+        assert_eq!(*cell.fetch_max_by(Arc::new(3), i32::cmp), 5);
+        assert_eq!(*cell.fetch_max_by(Arc::new(7), i32::cmp), 5);
+        assert_eq!(*cell.fetch_min_by(Arc::new(9), i32::cmp), 7);
+        assert_eq!(*cell.fetch_min_by(Arc::new(1), i32::cmp), 7);
+        assert_eq!(*cell.get(), 1);
+    }
+
+    #[test]
+    fn merge() {
+        let cell = Arc::new(ArcCell::from_value(Vec::new()));
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let cell = cell.clone();
+                std::thread::spawn(move || {
+                    cell.merge(Arc::new(vec![i]), |a, b| [&a[..], &b[..]].concat());
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let mut merged = cell.clone_inner();
+        merged.sort();
+        assert_eq!(merged, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn modify_or_insert() {
+        let cell = OptionalArcCell::new(None);
+        let insert = || Arc::new(1);
+        assert_eq!(*cell.modify_or_insert(|v| Arc::new(v + 1), insert), 1);
+        assert_eq!(*cell.modify_or_insert(|v| Arc::new(v + 1), insert), 2);
+        assert_eq!(cell.get(), Some(Arc::new(2)));
+    }
+
+    #[test]
+    fn eq() {
+        let shared = Arc::new(String::from("a"));
+        let cell = ArcCell::new(shared.clone());
+        assert_eq!(cell, ArcCell::new(shared));
+        assert_eq!(cell, ArcCell::new(Arc::new(String::from("a"))));
+        assert_ne!(cell, ArcCell::new(Arc::new(String::from("b"))));
+        assert_eq!(cell, cell);
+
+        #[derive(PartialEq, Eq, Debug)]
+        struct Config {
+            name: OptionalArcCell<String>,
+        }
+        let config = Config {
+            name: OptionalArcCell::new(None),
+        };
+        assert_eq!(
+            config,
+            Config {
+                name: OptionalArcCell::new(None)
+            }
+        );
+    }
+
+    #[test]
+    fn eq_arc() {
+        let installed = Arc::new(1);
+        let cell = ArcCell::new(installed.clone());
+        assert!(cell == installed);
+        assert!(cell != Arc::new(1));
+        cell.set(Arc::new(2));
+        assert!(cell != installed);
+    }
+
+    #[test]
+    fn hash() {
+        use std::{
+            collections::hash_map::DefaultHasher,
+            hash::{Hash, Hasher},
+        };
+        fn hash_of(value: &impl Hash) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+        let cell = ArcCell::new(Arc::new("a"));
+        assert_eq!(hash_of(&cell), hash_of(&Arc::new("a")));
+        assert_eq!(hash_of(&cell), hash_of(&ArcCell::new(Arc::new("a"))));
+        cell.set(Arc::new("b"));
+        assert_eq!(hash_of(&cell), hash_of(&Arc::new("b")));
+    }
+
+    #[test]
+    fn read() {
+        let cell = ArcCell::new(Arc::new(String::from("a")));
+        let guard = cell.read();
+        assert_eq!(guard.len(), 1);
+        cell.set(Arc::new(String::from("bc")));
+        assert_eq!(&*guard, "a");
+        assert_eq!(&*cell.read(), "bc");
+        assert_eq!(*ReadGuard::into_arc(guard), "a");
+    }
+
+    #[test]
+    fn const_new() {
+        static CELL: OptionalWeakCell<i32> = OptionalWeakCell::const_new();
+        let data = Arc::new(1);
+        CELL.store(&data);
+        assert_eq!(CELL.upgrade(), Some(data));
+    }
+
+    pub(crate) struct Unclonable(u32);
+
+    crate::arc_cell_static! {
+        static NAME: OptionalArcCell<String>;
+        pub(crate) static HANDLE: OptionalBoxCell<Unclonable>;
+    }
+
+    #[test]
+    fn arc_cell_static() {
+        assert!(NAME::get().is_none());
+        NAME::set(Some(Arc::new("first".to_string())));
+        assert_eq!(NAME.get().as_deref().map(String::as_str), Some("first"));
+        let old = NAME::swap(Some(Arc::new("second".to_string())));
+        assert_eq!(old.as_deref().map(String::as_str), Some("first"));
+        assert_eq!(NAME::get().as_deref().map(String::as_str), Some("second"));
+
+        HANDLE::set(Some(Box::new(Unclonable(1))));
+        assert_eq!(HANDLE::swap(None).map(|handle| handle.0), Some(1));
+        assert!(HANDLE.is_none());
+    }
+
+    #[test]
+    fn box_cell() {
+        let cell = BoxCell::new(Box::new(vec![1]));
+        let mut old = cell.set(Box::new(vec![2]));
+        old.push(3);
+        assert_eq!(*old, [1, 3]);
+        assert_eq!(*cell.get(), [2]);
+        assert_eq!(*cell.take(), [2]);
+        assert!(cell.get().is_empty());
+        let zst = BoxCell::new(Box::new(()));
+        assert_eq!(*zst.set(Box::new(())), ());
+    }
+
+    #[test]
+    fn box_cell_updates() {
+        let cell = Arc::new(BoxCell::new(Box::new(0)));
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        cell.fetch_update(|v| Some(Box::new(**v + 1))).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(*cell.get(), 400);
+        assert_eq!(*cell.fetch_update(|_| None).unwrap_err(), 400);
+        assert_eq!(*cell.get_and_update(|v| Box::new(**v * 2)), 400);
+        assert_eq!(*cell.update_and_get(|v| Box::new(**v + 1)), 801);
+
+        // A clone is a different allocation, so it never matches the stored Box.
+        let current = cell.get();
+        assert_eq!(
+            *cell.compare_exchange(&current, Box::new(0)).unwrap_err(),
+            0
+        );
+        assert_eq!(*cell.get(), 801);
+        let mailbox = OptionalBoxCell::new(None);
+        assert!(mailbox.compare_exchange(&None, Some(Box::new(1))).is_ok());
+    }
+
+    #[test]
+    fn box_cell_clone_panic() {
+        struct Fragile(u32);
+        impl Clone for Fragile {
+            fn clone(&self) -> Self {
+                panic!("can't clone {}", self.0)
+            }
+        }
+
+        let cell = BoxCell::new(Box::new(Fragile(1)));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| cell.get()));
+        assert!(result.is_err());
+        assert!(!cell.is_contended());
+        assert_eq!(cell.set(Box::new(Fragile(2))).0, 1);
+
+        let cells = (cell, BoxCell::new(Box::new(Fragile(3))));
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            snapshot2(&cells.0, &cells.1)
+        }));
+        assert!(result.is_err());
+        assert_eq!(cells.1.set(Box::new(Fragile(4))).0, 3);
+        assert_eq!(cells.0.into_inner().0, 2);
+    }
+
+    #[test]
+    fn pinned_box_cell() {
+        struct State {
+            frame: u32,
+            _pinned: std::marker::PhantomPinned,
+        }
+        fn state(frame: u32) -> Pin<Box<State>> {
+            Box::pin(State {
+                frame,
+                _pinned: std::marker::PhantomPinned,
+            })
+        }
+
+        let first = state(1);
+        let address = &*first as *const State;
+        let cell = AtomicCell::new(first);
+        let first = cell.set(state(2));
+        assert_eq!(&*first as *const State, address);
+        assert_eq!(first.frame, 1);
+        assert_eq!(cell.into_inner().frame, 2);
+
+        static CURRENT: AtomicCell<Option<Pin<Box<State>>>> = AtomicCell::const_new();
+        assert!(CURRENT.set(Some(state(3))).is_none());
+        assert_eq!(CURRENT.take().map(|state| state.frame), Some(3));
+    }
+
+    #[test]
+    fn ref_counted() {
+        use std::ptr::NonNull;
+        use std::sync::atomic::AtomicUsize;
+
+        struct Inner {
+            count: AtomicUsize,
+            value: u32,
+        }
+
+        // A minimal intrusive Arc, with the count stored next to the value.
+        struct Intrusive(NonNull<Inner>);
+        unsafe impl Send for Intrusive {}
+        unsafe impl Sync for Intrusive {}
+
+        impl Intrusive {
+            fn new(value: u32) -> Self {
+                let inner = Box::new(Inner {
+                    count: AtomicUsize::new(1),
+                    value,
+                });
+                Intrusive(NonNull::from(Box::leak(inner)))
+            }
+
+            fn inner(&self) -> &Inner {
+                unsafe { self.0.as_ref() }
+            }
+        }
+
+        impl Clone for Intrusive {
+            fn clone(&self) -> Self {
+                self.inner().count.fetch_add(1, Ordering::Relaxed);
+                Intrusive(self.0)
+            }
+        }
+
+        impl Drop for Intrusive {
+            fn drop(&mut self) {
+                if self.inner().count.fetch_sub(1, Ordering::AcqRel) == 1 {
+                    drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+                }
+            }
+        }
+
+        unsafe impl RefCounted for Intrusive {
+            type Target = Inner;
+
+            fn into_raw(this: Self) -> *const Inner {
+                std::mem::ManuallyDrop::new(this).0.as_ptr()
+            }
+
+            unsafe fn from_raw(ptr: *const Inner) -> Self {
+                Intrusive(NonNull::new_unchecked(ptr as *mut Inner))
+            }
+
+            fn as_ptr(this: &Self) -> *const Inner {
+                this.0.as_ptr()
+            }
+        }
+
+        impl_ref_counted!(Intrusive);
+
+        let first = Intrusive::new(1);
+        let cell = AtomicCell::new(first.clone());
+        assert_eq!(first.inner().count.load(Ordering::SeqCst), 2);
+        assert_eq!(cell.get().inner().value, 1);
+        assert_eq!(first.inner().count.load(Ordering::SeqCst), 2);
+        assert!(cell.compare_exchange(&first, Intrusive::new(2)).is_ok());
+        assert_eq!(first.inner().count.load(Ordering::SeqCst), 1);
+        assert_eq!(cell.get().inner().value, 2);
+
+        let arc = Arc::new(3);
+        assert_eq!(unsafe { *Arc::from_raw(RefCounted::into_raw(arc)) }, 3);
+    }
+
+    #[test]
+    fn mailbox() {
+        static MAILBOX: OptionalBoxCell<String> = OptionalBoxCell::const_new();
+        let sender = std::thread::spawn(|| {
+            let mut message = Box::new(String::from("hello"));
+            while let Err(returned) = MAILBOX.set_if_none(message) {
+                message = returned;
+                std::thread::yield_now();
+            }
+        });
+        let message = loop {
+            if let Some(message) = MAILBOX.take_some() {
+                break message;
+            }
+            std::thread::yield_now();
+        };
+        sender.join().unwrap();
+        assert_eq!(*message, "hello");
+        assert!(MAILBOX.is_none());
+    }
+
+    #[test]
+    fn static_cell() {
+        trait Strategy: Sync {
+            fn name(&self) -> &'static str;
+        }
+        struct Fast;
+        struct Safe;
+        impl Strategy for Fast {
+            fn name(&self) -> &'static str {
+                "fast"
+            }
+        }
+        impl Strategy for Safe {
+            fn name(&self) -> &'static str {
+                "safe"
+            }
+        }
+        static FAST: &dyn Strategy = &Fast;
+        static SAFE: &dyn Strategy = &Safe;
+
+        let cell = StaticCell::new(&FAST);
+        assert_eq!(cell.set(&SAFE).name(), "fast");
+        assert_eq!(cell.get().name(), "safe");
+        static NUMBER: i32 = 5;
+        let optional = AtomicCell::new(Some(&NUMBER));
+        assert_eq!(optional.take_some(), Some(&5));
+        let zst = StaticCell::new(&());
+        assert_eq!(*zst.get(), ());
+    }
+
+    #[test]
+    fn fn_cell() {
+        fn double(x: i32) -> i32 {
+            x * 2
+        }
+        fn negate(x: i32) -> i32 {
+            -x
+        }
+        let hook: FnCell<fn(i32) -> i32> = FnCell::new(double);
+        assert_eq!(hook.get()(3), 6);
+        assert_eq!(hook.set(negate)(3), 6);
+        assert_eq!(hook.get()(3), -3);
+
+        static ON_EVENT: FnCell<Option<fn(&'static str)>> = FnCell::const_new();
+        assert!(ON_EVENT.is_none());
+        ON_EVENT.set(Some(|event| assert_eq!(event, "start")));
+        ON_EVENT.get().unwrap()("start");
+    }
+
+    #[test]
+    fn storable_enum() {
+        crate::storable_enum! {
+            #[derive(Clone, Debug, Default, PartialEq)]
+            #[repr(i8)]
+            enum State {
+                Idle = -1,
+                #[default]
+                Running = 5,
+                Stopped,
+            }
+        }
+        let cell = AtomicCell::new(State::default());
+        assert_eq!(cell.set(State::Stopped), State::Running);
+        assert_eq!(
+            cell.compare_exchange(&State::Stopped, State::Idle),
+            Ok(State::Stopped)
+        );
+        assert_eq!(cell.get(), State::Idle);
+        assert_eq!(unsafe { cell.as_atomic() }.load(Ordering::SeqCst), 0);
+        static CONST: AtomicCell<State> = AtomicCell::const_new();
+        assert_eq!(CONST.get(), State::Idle);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_storable() {
+        #[derive(crate::AtomicCellStorable, Clone, Copy, Debug, PartialEq)]
+        enum Mode {
+            Normal = 3,
+            Degraded = 1,
+            Maintenance,
+        }
+
+        static MODE: AtomicCell<Mode> = AtomicCell::const_new();
+        assert_eq!(MODE.get(), Mode::Normal);
+        assert_eq!(MODE.set(Mode::Maintenance), Mode::Normal);
+        assert_eq!(unsafe { MODE.as_atomic() }.load(Ordering::SeqCst), 2);
+        assert_eq!(MODE.set(Mode::Degraded), Mode::Maintenance);
+        assert_eq!(MODE.get(), Mode::Degraded);
+    }
+
+    #[test]
+    fn result_cell() {
+        #[derive(Clone, Debug, PartialEq)]
+        enum Failure {
+            NotFound,
+            Timeout,
+        }
+        impl ErrorCode for Failure {
+            fn to_code(&self) -> u16 {
+                self.clone() as u16
+            }
+            fn from_code(code: u16) -> Self {
+                match code {
+                    0 => Failure::NotFound,
+                    _ => Failure::Timeout,
+                }
+            }
+        }
+        let cell = ResultCell::new(Err(Failure::Timeout));
+        assert_eq!(cell.get(), Err(Failure::Timeout));
+        assert_eq!(cell.set(Ok(Arc::new(5))), Err(Failure::Timeout));
+        assert_eq!(cell.set(Err(Failure::NotFound)), Ok(Arc::new(5)));
+        assert_eq!(cell.get(), Err(Failure::NotFound));
+        let codes: ResultCell<(), u16> = ResultCell::new(Err(u16::MAX));
+        assert_eq!(codes.get(), Err(u16::MAX));
+    }
+
+    #[test]
+    fn dyn_arc_cell() {
+        trait Strategy: Send + Sync {
+            fn run(&self) -> i32;
+        }
+        struct Constant(i32);
+        impl Strategy for Constant {
+            fn run(&self) -> i32 {
+                self.0
+            }
+        }
+
+        let first: Arc<dyn Strategy> = Arc::new(Constant(1));
+        let other: Arc<dyn Strategy> = Arc::new(Constant(1));
+        let cell: ArcCell<dyn Strategy> = ArcCell::new(first.clone());
+        assert_eq!(cell.get().run(), 1);
+        assert_eq!(cell.with(|strategy| strategy.run()), 1);
+        assert!(cell
+            .compare_exchange(&other, Arc::new(Constant(2)))
+            .is_err());
+        let old = cell.compare_exchange(&first, Arc::new(Constant(2)));
+        assert_eq!(old.ok().map(|strategy| strategy.run()), Some(1));
+        assert_eq!(Arc::strong_count(&first), 1);
+        assert_eq!(cell.set(first).run(), 2);
+
+        let current = cell.get();
+        assert!(cell
+            .compare_exchange_weak(&other, Arc::new(Constant(3)))
+            .is_err());
+        assert!(Arc::ptr_eq(&cell.get(), &current));
+        let mut new: Arc<dyn Strategy> = Arc::new(Constant(3));
+        let old = loop {
+            match cell.compare_exchange_weak(&current, new) {
+                Ok(old) => break old,
+                Err(back) => new = back,
+            }
+        };
+        assert!(Arc::ptr_eq(&old, &current));
+        drop(old);
+        assert_eq!(Arc::strong_count(&current), 1);
+        assert_eq!(cell.get().run(), 3);
+
+        let optional: OptionalArcCell<dyn Strategy> = OptionalArcCell::const_new();
+        assert!(optional.set_if_none(Arc::new(Constant(3))).is_ok());
+        assert_eq!(optional.take_some().map(|strategy| strategy.run()), Some(3));
+    }
+
+    #[test]
+    fn slice_arc_cell() {
+        let name: ArcCell<str> = ArcCell::new(Arc::from("primary"));
+        assert_eq!(&*name.set(Arc::from("replica")), "primary");
+        assert_eq!(name.to_string(), "replica");
+        // Reads leave the boxed Arc where it is.
+        let boxed = name.value.load(Ordering::SeqCst);
+        let replica = name.get();
+        assert_eq!(name.with(|name| name.len()), 7);
+        assert_eq!(name.strong_count(), 2);
+        drop(replica);
+        assert_eq!(name.strong_count(), 1);
+        assert_eq!(name.try_get().as_deref(), Some("replica"));
+        assert_eq!(name.value.load(Ordering::SeqCst), boxed);
+
+        let weak: OptionalWeakCell<str> = OptionalWeakCell::new(None);
+        let target: Arc<str> = Arc::from("target");
+        weak.store(&target);
+        let boxed = weak.value.load(Ordering::SeqCst);
+        assert_eq!(weak.upgrade().as_deref(), Some("target"));
+        assert_eq!(weak.value.load(Ordering::SeqCst), boxed);
+        assert_eq!(Arc::weak_count(&target), 1);
+
+        let buffer: OptionalArcCell<[u8]> = OptionalArcCell::new(None);
+        buffer.set(Some(Arc::from(&[1, 2, 3][..])));
+        assert_eq!(buffer.get().as_deref(), Some(&[1, 2, 3][..]));
+        let snapshot = buffer.get();
+        assert!(buffer.compare_exchange(&snapshot, None).is_ok());
+        assert!(buffer.is_none());
+    }
+
+    #[test]
+    fn any_arc_cell() {
+        let cell = AnyArcCell::const_new();
+        assert_eq!(cell.get_downcast::<i32>(), None);
+        cell.set_any(Arc::new(5));
+        assert!(cell.is::<i32>());
+        assert_eq!(cell.get_downcast::<i32>(), Some(Arc::new(5)));
+        assert_eq!(cell.get_downcast::<String>(), None);
+        let old = cell.set_any(Arc::new(String::from("plugin")));
+        assert_eq!(
+            old.and_then(|old| old.downcast::<i32>().ok()),
+            Some(Arc::new(5))
+        );
+        assert_eq!(
+            cell.get_downcast::<String>().as_deref().map(String::as_str),
+            Some("plugin")
+        );
+    }
+
+    #[test]
+    fn dyn_weak_cell() {
+        trait Observer: Send + Sync {
+            fn id(&self) -> u32;
+        }
+        struct Listener(u32);
+        impl Observer for Listener {
+            fn id(&self) -> u32 {
+                self.0
+            }
+        }
+
+        let listener: Arc<dyn Observer> = Arc::new(Listener(1));
+        let cell: WeakCell<dyn Observer> = WeakCell::from_arc(&listener);
+        assert_eq!(cell.upgrade().map(|observer| observer.id()), Some(1));
+        let current = cell.get();
+        assert!(cell
+            .compare_exchange(&current, Arc::downgrade(&listener))
+            .is_ok());
+        drop(listener);
+        assert!(cell.upgrade().is_none());
+
+        let names: Arc<[&str]> = Arc::from(&["a", "b"][..]);
+        let optional: OptionalWeakCell<[&str]> = OptionalWeakCell::const_new();
+        optional.store(&names);
+        assert_eq!(optional.upgrade().as_deref(), Some(&["a", "b"][..]));
+        assert_eq!(
+            optional.upgrade_and_clear().map(|names| names.len()),
+            Some(2)
+        );
+        assert!(optional.is_none());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(ArcCell::new(Arc::new(5)).to_string(), "5");
+        assert_eq!(format!("{:>3}", ArcCell::new(Arc::new(5))), "  5");
+        assert_eq!(OptionalArcCell::new(Some(Arc::new("on"))).to_string(), "on");
+        assert_eq!(OptionalArcCell::<i32>::new(None).to_string(), "<empty>");
     }
 
     #[test]
@@ -291,5 +3713,14 @@ mod tests {
             let _cell = ArcCell::new(Arc::new(DropCount));
         }
         assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+
+        let cell = OptionalArcCell::new(Some(Arc::new(DropCount)));
+        drop(cell.take());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+        cell.set(Some(Arc::new(DropCount)));
+        drop(cell.set_default());
+        assert_eq!(DROPS.load(Ordering::SeqCst), 3);
+        drop(cell);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 3);
     }
 }
@@ -0,0 +1,306 @@
+use crate::{AtomicCellConstInit, AtomicCellStorable, RefCounted};
+use std::{
+    cell::Cell,
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    rc::{Rc, Weak},
+};
+
+/// A single-threaded version of [`AtomicCell`](crate::AtomicCell), backed by a plain
+/// `Cell<usize>` instead of an atomic.
+///
+/// It can hold any [`AtomicCellStorable`] value, including `Rc` and `rc::Weak` pointers. The
+/// value is still taken out of the cell while it is being accessed, so instead of seeing a
+/// half-updated cell, re-entrant access (e.g. from a `Clone` impl that touches the cell again)
+/// panics.
+pub struct LocalCell<T: AtomicCellStorable> {
+    value: Cell<usize>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: AtomicCellStorable> LocalCell<T> {
+    /// Create a new LocalCell with the given initial value.
+    pub fn new(value: T) -> Self {
+        LocalCell {
+            value: Cell::new(value.into_value()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Replace the value in the cell, returning the old value.
+    pub fn set(&self, value: T) -> T {
+        let old = self.internal_take();
+        self.internal_put(value);
+        old
+    }
+
+    /// Calls `f` with a reference to the stored value, returning its result.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let value = Restore {
+            value: ManuallyDrop::new(self.internal_take()),
+            cell: self,
+        };
+        f(&value.value)
+    }
+
+    /// Swap the values stored in two cells.
+    pub fn swap(&self, other: &LocalCell<T>) {
+        if std::ptr::eq(self, other) {
+            return;
+        }
+        let first = self.internal_take();
+        let second = other.internal_take();
+        self.internal_put(second);
+        other.internal_put(first);
+    }
+
+    /// Consumes the cell, returning the stored value.
+    pub fn into_inner(self) -> T {
+        let this = ManuallyDrop::new(self);
+        this.internal_take()
+    }
+
+    fn internal_take(&self) -> T {
+        let value = self.value.replace(T::TAKEN_VALUE);
+        assert_ne!(
+            value,
+            T::TAKEN_VALUE,
+            "LocalCell was accessed while it was already being accessed"
+        );
+        unsafe { T::from_value(value) }
+    }
+
+    fn internal_put(&self, value: T) {
+        self.value.set(value.into_value());
+    }
+}
+
+impl<T: AtomicCellStorable + AtomicCellConstInit> LocalCell<T> {
+    /// Create a new LocalCell holding the default value, usable in `thread_local!`s.
+    pub const fn const_new() -> Self {
+        LocalCell {
+            value: Cell::new(T::DEFAULT_VALUE),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: AtomicCellStorable + Clone> LocalCell<T> {
+    /// Returns a clone of the stored value.
+    pub fn get(&self) -> T {
+        self.with(T::clone)
+    }
+}
+
+impl<T: AtomicCellStorable + Default> LocalCell<T> {
+    /// Takes the value out of the cell, leaving a default value in its place.
+    pub fn take(&self) -> T {
+        self.set(T::default())
+    }
+}
+
+impl<T> LocalCell<Rc<T>> {
+    /// Returns whether the cell currently points to the same allocation as `other`.
+    pub fn ptr_eq(&self, other: &Rc<T>) -> bool {
+        self.with(|value| Rc::ptr_eq(value, other))
+    }
+
+    /// Returns a Weak pointer to the stored value.
+    pub fn downgrade(&self) -> Weak<T> {
+        self.with(Rc::downgrade)
+    }
+}
+
+impl<T> LocalCell<Weak<T>> {
+    /// Upgrades the stored Weak pointer, returning None if the value has been dropped.
+    pub fn upgrade(&self) -> Option<Rc<T>> {
+        self.with(Weak::upgrade)
+    }
+
+    /// Stores a Weak pointer to `rc` in the cell.
+    pub fn store(&self, rc: &Rc<T>) {
+        self.set(Rc::downgrade(rc));
+    }
+}
+
+impl<P> LocalCell<Option<P>>
+where
+    Option<P>: AtomicCellStorable,
+{
+    /// Returns whether the cell currently holds a value.
+    pub fn is_some(&self) -> bool {
+        self.with(Option::is_some)
+    }
+
+    /// Returns whether the cell is currently empty.
+    pub fn is_none(&self) -> bool {
+        !self.is_some()
+    }
+
+    /// Takes the value out of the cell, leaving it empty.
+    pub fn take_some(&self) -> Option<P> {
+        self.set(None)
+    }
+}
+
+impl<T: AtomicCellStorable> Drop for LocalCell<T> {
+    fn drop(&mut self) {
+        let value = self.value.get();
+        // The cell can only be taken here if a panic happened while accessing it.
+        if value != T::TAKEN_VALUE {
+            unsafe {
+                let _ = T::from_value(value);
+            }
+        }
+    }
+}
+
+impl<T: AtomicCellStorable + Default> Default for LocalCell<T> {
+    fn default() -> Self {
+        LocalCell::new(T::default())
+    }
+}
+
+impl<T: AtomicCellStorable> From<T> for LocalCell<T> {
+    fn from(value: T) -> Self {
+        LocalCell::new(value)
+    }
+}
+
+impl<T: AtomicCellStorable + Debug> Debug for LocalCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        self.with(|value| fmt.debug_tuple("LocalCell").field(value).finish())
+    }
+}
+
+/// Puts the taken value back when dropped, even if the thread panics.
+struct Restore<'a, T: AtomicCellStorable> {
+    cell: &'a LocalCell<T>,
+    value: ManuallyDrop<T>,
+}
+
+impl<T: AtomicCellStorable> Drop for Restore<'_, T> {
+    fn drop(&mut self) {
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        self.cell.internal_put(value);
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Rc<T> {
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        Rc::into_raw(self) as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        Rc::from_raw(value as *const T)
+    }
+}
+
+unsafe impl<T> RefCounted for Rc<T> {
+    type Target = T;
+
+    fn into_raw(this: Self) -> *const T {
+        Rc::into_raw(this)
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Rc::from_raw(ptr)
+    }
+
+    fn as_ptr(this: &Self) -> *const T {
+        Rc::as_ptr(this)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Weak<T> {
+    // This must be MAX-1 because MAX is the sentinel value Weak uses for the empty state.
+    const TAKEN_VALUE: usize = usize::MAX - 1;
+
+    fn into_value(self) -> usize {
+        Weak::into_raw(self) as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        Weak::from_raw(value as *const T)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<Rc<T>> {
+    const TAKEN_VALUE: usize = <Rc<T> as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => crate::EMPTY_OPTION,
+            Some(rc) => Rc::into_raw(rc) as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            crate::EMPTY_OPTION => None,
+            value => Some(Rc::from_raw(value as *const T)),
+        }
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<Weak<T>> {
+    const TAKEN_VALUE: usize = <Weak<T> as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => crate::EMPTY_OPTION,
+            Some(weak) => Weak::into_raw(weak) as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            crate::EMPTY_OPTION => None,
+            value => Some(Weak::from_raw(value as *const T)),
+        }
+    }
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<Rc<T>> {
+    const DEFAULT_VALUE: usize = crate::EMPTY_OPTION;
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<Weak<T>> {
+    const DEFAULT_VALUE: usize = crate::EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocalCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn local_cell() {
+        use std::rc::Rc;
+
+        let data = Rc::new(5);
+        let cell = LocalCell::new(data.clone());
+        assert!(cell.ptr_eq(&data));
+        assert_eq!(*cell.set(Rc::new(6)), 5);
+        assert_eq!(*cell.get(), 6);
+
+        let weak = LocalCell::new(cell.downgrade());
+        assert_eq!(weak.upgrade(), Some(Rc::new(6)));
+        drop(cell);
+        assert_eq!(weak.upgrade(), None);
+
+        let optional = LocalCell::new(Some(data));
+        assert_eq!(optional.take_some(), Some(Rc::new(5)));
+        assert!(optional.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "already being accessed")]
+    fn local_cell_reentrant() {
+        let cell = LocalCell::new(Arc::new(1));
+        cell.with(|_| cell.get());
+    }
+}
@@ -0,0 +1,277 @@
+//! Operations spanning several cells at once.
+
+use crate::{AtomicCell, AtomicCellStorable};
+use std::mem::ManuallyDrop;
+
+/// Type-erased access to the raw value of a cell, so cells of different types can be taken
+/// together.
+trait Slot {
+    fn take_raw(&self) -> usize;
+    fn restore_raw(&self, value: usize);
+    fn notify(&self);
+}
+
+impl<T: AtomicCellStorable> Slot for AtomicCell<T> {
+    fn take_raw(&self) -> usize {
+        self.internal_take().into_value()
+    }
+
+    fn restore_raw(&self, value: usize) {
+        self.internal_restore(unsafe { T::from_value(value) });
+    }
+
+    fn notify(&self) {
+        AtomicCell::notify(self);
+    }
+}
+
+fn address(slot: &dyn Slot) -> usize {
+    slot as *const dyn Slot as *const () as usize
+}
+
+/// Takes all of the cells, always in order of their addresses so that concurrent callers can't
+/// deadlock. A cell that appears more than once is only taken once, and its raw value is
+/// returned at each of its positions.
+fn take_all<const N: usize>(slots: [&dyn Slot; N]) -> [usize; N] {
+    let mut order: [usize; N] = std::array::from_fn(|i| i);
+    order.sort_unstable_by_key(|&i| address(slots[i]));
+
+    let mut values = [0; N];
+    let mut previous: Option<usize> = None;
+    for i in order {
+        values[i] = match previous {
+            Some(p) if address(slots[p]) == address(slots[i]) => values[p],
+            _ => slots[i].take_raw(),
+        };
+        previous = Some(i);
+    }
+    values
+}
+
+/// Puts raw values into cells taken with [`take_all`]. A cell that appears more than once gets
+/// the value at its last position.
+fn restore_all<const N: usize>(slots: [&dyn Slot; N], values: [usize; N]) {
+    for i in 0..N {
+        if !repeated_later(&slots, i) {
+            slots[i].restore_raw(values[i]);
+        }
+    }
+}
+
+/// Like [`restore_all`], but for new values, waking any threads waiting for the cells to change.
+fn put_all<const N: usize>(slots: [&dyn Slot; N], values: [usize; N]) {
+    restore_all(slots, values);
+    for i in 0..N {
+        if !repeated_later(&slots, i) {
+            slots[i].notify();
+        }
+    }
+}
+
+fn repeated_later(slots: &[&dyn Slot], i: usize) -> bool {
+    slots[i + 1..]
+        .iter()
+        .any(|&slot| address(slot) == address(slots[i]))
+}
+
+/// Swaps new raw values in for the taken ones, returning the values that were replaced. When a
+/// cell appears more than once, each position replaces the value stored by the one before it.
+fn replace_all<const N: usize>(
+    slots: [&dyn Slot; N],
+    mut current: [usize; N],
+    new: [usize; N],
+) -> [usize; N] {
+    let mut old = [0; N];
+    for i in 0..N {
+        old[i] = current[i];
+        for j in i..N {
+            if address(slots[j]) == address(slots[i]) {
+                current[j] = new[i];
+            }
+        }
+    }
+    put_all(slots, current);
+    old
+}
+
+/// Cells taken with [`take_all`], which are restored when this is dropped, even if cloning one
+/// of their values panics.
+struct TakenAll<'a, const N: usize> {
+    slots: [&'a dyn Slot; N],
+    values: [usize; N],
+}
+
+impl<'a, const N: usize> TakenAll<'a, N> {
+    fn new(slots: [&'a dyn Slot; N]) -> Self {
+        TakenAll {
+            slots,
+            values: take_all(slots),
+        }
+    }
+
+    /// Clones the value at position `i`, which must be a cell of type `AtomicCell<T>`.
+    ///
+    /// `from_value` consumes the raw value and indirect storables get a new one from
+    /// `into_value`, so the value is converted back afterwards and every position of the same
+    /// cell is updated to the new raw value.
+    unsafe fn clone_at<T: AtomicCellStorable + Clone>(&mut self, i: usize) -> T {
+        let value = Reencode {
+            value: ManuallyDrop::new(T::from_value(self.values[i])),
+            taken: self,
+            i,
+        };
+        T::clone(&value.value)
+    }
+}
+
+impl<const N: usize> Drop for TakenAll<'_, N> {
+    fn drop(&mut self) {
+        restore_all(self.slots, self.values);
+    }
+}
+
+/// Converts a value decoded by [`TakenAll::clone_at`] back into its raw value when dropped.
+struct Reencode<'t, 'a, T: AtomicCellStorable, const N: usize> {
+    taken: &'t mut TakenAll<'a, N>,
+    i: usize,
+    value: ManuallyDrop<T>,
+}
+
+impl<T: AtomicCellStorable, const N: usize> Drop for Reencode<'_, '_, T, N> {
+    fn drop(&mut self) {
+        let raw = unsafe { ManuallyDrop::take(&mut self.value) }.into_value();
+        let slots = self.taken.slots;
+        for j in 0..N {
+            if address(slots[j]) == address(slots[self.i]) {
+                self.taken.values[j] = raw;
+            }
+        }
+    }
+}
+
+/// Returns clones of the values of both cells, as they were at a single point in time.
+///
+/// Both cells are taken before either is cloned, so this can't observe a new value in one cell
+/// alongside an old value in the other if they are updated together. Cells are taken in order of
+/// their addresses, and passing the same cell twice is fine.
+pub fn snapshot2<A, B>(a: &AtomicCell<A>, b: &AtomicCell<B>) -> (A, B)
+where
+    A: AtomicCellStorable + Clone,
+    B: AtomicCellStorable + Clone,
+{
+    let mut taken = TakenAll::new([a, b]);
+    unsafe { (taken.clone_at(0), taken.clone_at(1)) }
+}
+
+/// Returns clones of the values of all three cells, as they were at a single point in time.
+///
+/// See [`snapshot2`].
+pub fn snapshot3<A, B, C>(a: &AtomicCell<A>, b: &AtomicCell<B>, c: &AtomicCell<C>) -> (A, B, C)
+where
+    A: AtomicCellStorable + Clone,
+    B: AtomicCellStorable + Clone,
+    C: AtomicCellStorable + Clone,
+{
+    let mut taken = TakenAll::new([a, b, c]);
+    unsafe { (taken.clone_at(0), taken.clone_at(1), taken.clone_at(2)) }
+}
+
+/// Stores new values into all of the cells at once, returning the values that were replaced.
+///
+/// All of the cells are taken before any of them is given its new value, so readers using
+/// [`snapshot2`] or [`snapshot3`] will either see all of the old values or all of the new ones.
+/// If a cell appears more than once it ends up with the last value given for it.
+pub fn set_all<T, const N: usize>(cells: [(&AtomicCell<T>, T); N]) -> [T; N]
+where
+    T: AtomicCellStorable,
+{
+    let slots: [&dyn Slot; N] = std::array::from_fn(|i| cells[i].0 as &dyn Slot);
+    let new = cells.map(|(_, value)| value.into_value());
+    let old = replace_all(slots, take_all(slots), new);
+    old.map(|value| unsafe { T::from_value(value) })
+}
+
+/// Stores new values into both cells at once, returning the values that were replaced.
+///
+/// See [`set_all`].
+pub fn publish2<A, B>(a: (&AtomicCell<A>, A), b: (&AtomicCell<B>, B)) -> (A, B)
+where
+    A: AtomicCellStorable,
+    B: AtomicCellStorable,
+{
+    let slots: [&dyn Slot; 2] = [a.0, b.0];
+    let new = [a.1.into_value(), b.1.into_value()];
+    let old = replace_all(slots, take_all(slots), new);
+    unsafe { (A::from_value(old[0]), B::from_value(old[1])) }
+}
+
+/// Stores new values into all three cells at once, returning the values that were replaced.
+///
+/// See [`set_all`].
+pub fn publish3<A, B, C>(
+    a: (&AtomicCell<A>, A),
+    b: (&AtomicCell<B>, B),
+    c: (&AtomicCell<C>, C),
+) -> (A, B, C)
+where
+    A: AtomicCellStorable,
+    B: AtomicCellStorable,
+    C: AtomicCellStorable,
+{
+    let slots: [&dyn Slot; 3] = [a.0, b.0, c.0];
+    let new = [a.1.into_value(), b.1.into_value(), c.1.into_value()];
+    let old = replace_all(slots, take_all(slots), new);
+    unsafe {
+        (
+            A::from_value(old[0]),
+            B::from_value(old[1]),
+            C::from_value(old[2]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{publish2, set_all, snapshot2, snapshot3, ArcCell, OptionalArcCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn snapshots() {
+        let a = ArcCell::from_value(1);
+        let b = OptionalArcCell::from_value("b");
+        assert_eq!(snapshot2(&a, &b), (Arc::new(1), Some(Arc::new("b"))));
+        assert_eq!(snapshot2(&a, &a), (Arc::new(1), Arc::new(1)));
+        assert_eq!(snapshot3(&b, &a, &b).1, Arc::new(1));
+        assert_eq!(a.strong_count(), 1);
+        assert_eq!(*a.get(), 1);
+    }
+
+    #[test]
+    fn unsized_snapshots() {
+        // Unsized Arcs are boxed, so cloning them must not free the box still in the cell.
+        let name: ArcCell<str> = ArcCell::new(Arc::from("a"));
+        let items: OptionalArcCell<[u8]> = OptionalArcCell::new(Some(Arc::from(&[1, 2][..])));
+        let (copy, _) = snapshot2(&name, &items);
+        assert_eq!(&*copy, "a");
+        let (first, second) = snapshot2(&name, &name);
+        assert!(Arc::ptr_eq(&first, &second));
+        let (_, items_copy, _) = snapshot3(&items, &items, &name);
+        assert_eq!(items_copy.as_deref(), Some(&[1, 2][..]));
+        assert_eq!(&*name.get(), "a");
+        assert_eq!(Arc::strong_count(&copy), 4);
+    }
+
+    #[test]
+    fn publish() {
+        let a = ArcCell::from_value(1);
+        let b = OptionalArcCell::from_value("b");
+        let (old_a, old_b) = publish2((&a, Arc::new(2)), (&b, None));
+        assert_eq!((*old_a, old_b), (1, Some(Arc::new("b"))));
+        assert_eq!(snapshot2(&a, &b), (Arc::new(2), None));
+
+        let c = ArcCell::from_value(3);
+        let old = set_all([(&a, Arc::new(4)), (&c, Arc::new(5)), (&a, Arc::new(6))]);
+        assert_eq!(old.map(|v| *v), [2, 3, 4]);
+        assert_eq!((*a.get(), *c.get()), (6, 5));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{AtomicCellConstInit, AtomicCellStorable};
+use std::{
+    convert::TryFrom,
+    fmt::{Debug, Display, Formatter},
+};
+
+/// The error returned when converting the maximum value of an integer into a non-max integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxValueError;
+
+impl Display for MaxValueError {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.write_str("the maximum value can't be stored in a non-max integer")
+    }
+}
+
+impl std::error::Error for MaxValueError {}
+
+macro_rules! nonmax {
+    ($(#[$attr:meta])* $name:ident($int:ty)) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name($int);
+
+        impl $name {
+            /// Zero.
+            pub const ZERO: $name = $name(0);
+
+            /// Returns the integer as a non-max integer, or None if it is the maximum value.
+            pub const fn new(value: $int) -> Option<Self> {
+                if value == <$int>::MAX {
+                    None
+                } else {
+                    Some($name(value))
+                }
+            }
+
+            /// Returns the integer value.
+            pub const fn get(self) -> $int {
+                self.0
+            }
+        }
+
+        impl TryFrom<$int> for $name {
+            type Error = MaxValueError;
+
+            fn try_from(value: $int) -> Result<Self, MaxValueError> {
+                $name::new(value).ok_or(MaxValueError)
+            }
+        }
+
+        impl From<$name> for $int {
+            fn from(value: $name) -> $int {
+                value.0
+            }
+        }
+
+        impl Debug for $name {
+            fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+                Debug::fmt(&self.0, fmt)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+                Display::fmt(&self.0, fmt)
+            }
+        }
+
+        unsafe impl AtomicCellStorable for $name {
+            // The integer is stored as is, so the maximum value is free to be the sentinel.
+            const TAKEN_VALUE: usize = usize::MAX;
+
+            fn into_value(self) -> usize {
+                self.0 as usize
+            }
+
+            unsafe fn from_value(value: usize) -> Self {
+                $name(value as $int)
+            }
+        }
+
+        unsafe impl AtomicCellConstInit for $name {
+            const DEFAULT_VALUE: usize = 0;
+        }
+    };
+}
+
+nonmax! {
+    /// A `usize` which is never `usize::MAX`, so that it can be stored in a
+    /// [`ValueCell`](crate::ValueCell).
+    NonMaxUsize(usize)
+}
+
+nonmax! {
+    /// A `u32` which is never `u32::MAX`, so that it can be stored in a
+    /// [`ValueCell`](crate::ValueCell).
+    NonMaxU32(u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MaxValueError, NonMaxU32, NonMaxUsize, ValueCell};
+
+    #[test]
+    fn value_cell() {
+        static EPOCH: ValueCell = ValueCell::const_new();
+        assert_eq!(EPOCH.get(), NonMaxUsize::ZERO);
+        EPOCH
+            .fetch_update(|epoch| NonMaxUsize::new(epoch.get() + 1))
+            .unwrap();
+        assert_eq!(EPOCH.get().get(), 1);
+
+        let index = ValueCell::new(NonMaxU32::new(u32::MAX - 1).unwrap());
+        assert_eq!(u32::from(index.get()), u32::MAX - 1);
+        assert_eq!(
+            std::convert::TryFrom::try_from(u32::MAX),
+            Err::<NonMaxU32, _>(MaxValueError)
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! A minimal parking lot for threads waiting for a cell to be modified.
+
+use std::{
+    sync::{
+        atomic::{fence, AtomicUsize, Ordering},
+        Mutex, MutexGuard,
+    },
+    thread::{self, Thread, ThreadId},
+};
+
+/// How many shards the waiters are spread over, must be a power of two.
+const SHARDS: usize = 64;
+
+/// Waiters for the keys that hash to one shard. Each shard sits on its own cache line so that
+/// notifying one cell doesn't read a line written by threads waiting on unrelated cells.
+#[repr(align(128))]
+struct Shard {
+    /// The number of registered waiters, so notifying is just a load when nobody is waiting.
+    waiting: AtomicUsize,
+    waiters: Mutex<Vec<(usize, Thread)>>,
+}
+
+impl Shard {
+    const fn new() -> Self {
+        Shard {
+            waiting: AtomicUsize::new(0),
+            waiters: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn waiters(&self) -> MutexGuard<'_, Vec<(usize, Thread)>> {
+        // The list is never left in an inconsistent state, so poisoning can be ignored.
+        self.waiters
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+static TABLE: [Shard; SHARDS] = {
+    // Only used to initialize the array below, it is never shared.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: Shard = Shard::new();
+    [EMPTY; SHARDS]
+};
+
+fn shard(key: usize) -> &'static Shard {
+    // Keys are addresses of cells, so mix the bits to keep neighbouring cells apart.
+    let hash = (key as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    &TABLE[(hash >> (64 - SHARDS.trailing_zeros())) as usize]
+}
+
+/// The current thread's entry in a shard, removed again when dropped even if the predicate
+/// passed to [`wait_until`] panics.
+struct Registration {
+    shard: &'static Shard,
+    key: usize,
+    thread: ThreadId,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // Only makes `notify` skip the shard sooner, so it doesn't have to be ordered.
+        self.shard.waiting.fetch_sub(1, Ordering::Relaxed);
+        let mut waiters = self.shard.waiters();
+        if let Some(index) = waiters
+            .iter()
+            .position(|(k, thread)| *k == self.key && thread.id() == self.thread)
+        {
+            waiters.swap_remove(index);
+        }
+    }
+}
+
+/// Parks the current thread until `ready` returns true, re-checking it every time [`notify`] is
+/// called with the same `key`.
+pub(crate) fn wait_until<F>(key: usize, mut ready: F)
+where
+    F: FnMut() -> bool,
+{
+    if ready() {
+        return;
+    }
+
+    let shard = shard(key);
+    let current = thread::current();
+    shard.waiters().push((key, current.clone()));
+    // Pairs with the fence in `notify`: either the notifying thread sees us registered, or we
+    // see its modification when checking `ready` below.
+    shard.waiting.fetch_add(1, Ordering::SeqCst);
+    let _registration = Registration {
+        shard,
+        key,
+        thread: current.id(),
+    };
+
+    // We must check again after registering, otherwise we could miss a notification that
+    // happened in between.
+    while !ready() {
+        thread::park();
+    }
+}
+
+/// Wakes all threads waiting on `key`.
+pub(crate) fn notify(key: usize) {
+    let shard = shard(key);
+    // Cells publish their modifications with Release stores, which a SeqCst load alone
+    // wouldn't order before itself, so the fence keeps the load from overtaking them.
+    fence(Ordering::SeqCst);
+    if shard.waiting.load(Ordering::SeqCst) == 0 {
+        return;
+    }
+
+    for (_, thread) in shard.waiters().iter().filter(|(k, _)| *k == key) {
+        thread.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{notify, shard, wait_until};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn wake_waiter() {
+        let key = 0x1000;
+        let ready = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let ready = ready.clone();
+            std::thread::spawn(move || wait_until(key, || ready.load(Ordering::SeqCst)))
+        };
+        while shard(key).waiting.load(Ordering::SeqCst) == 0 {
+            std::thread::yield_now();
+        }
+        ready.store(true, Ordering::SeqCst);
+        notify(key);
+        waiter.join().unwrap();
+        assert!(shard(key).waiters().iter().all(|(k, _)| *k != key));
+    }
+
+    #[test]
+    fn panicking_predicate() {
+        let key = 0x2000;
+        let mut checks = 0;
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            wait_until(key, || {
+                checks += 1;
+                if checks == 2 {
+                    panic!("predicate failed");
+                }
+                false
+            })
+        }));
+        assert!(result.is_err());
+        assert!(shard(key).waiters().iter().all(|(k, _)| *k != key));
+    }
+}
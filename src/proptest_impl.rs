@@ -0,0 +1,42 @@
+//! `proptest` support, generating cells holding an arbitrary value.
+
+use crate::{ArcCell, OptionalArcCell};
+use proptest::{
+    arbitrary::{any_with, Arbitrary, ParamsFor, StrategyFor},
+    strategy::{Map, Strategy},
+};
+use std::sync::Arc;
+
+impl<T: Arbitrary> Arbitrary for ArcCell<T> {
+    type Parameters = ParamsFor<T>;
+    type Strategy = Map<StrategyFor<T>, fn(T) -> Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        any_with::<T>(args).prop_map(|value| ArcCell::new(Arc::new(value)))
+    }
+}
+
+impl<T: Arbitrary> Arbitrary for OptionalArcCell<T> {
+    type Parameters = ParamsFor<Option<T>>;
+    type Strategy = Map<StrategyFor<Option<T>>, fn(Option<T>) -> Self>;
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        any_with::<Option<T>>(args).prop_map(|value| OptionalArcCell::new(value.map(Arc::new)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, OptionalArcCell};
+    use std::sync::Arc;
+
+    proptest::proptest! {
+        #[test]
+        fn arbitrary(cell: ArcCell<u8>, optional: OptionalArcCell<u8>) {
+            let value = *cell.get();
+            cell.set(Arc::new(value.wrapping_add(1)));
+            proptest::prop_assert_eq!(*cell.get(), value.wrapping_add(1));
+            proptest::prop_assert_eq!(optional.is_some(), optional.get().is_some());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{AtomicCell, AtomicCellConstInit, AtomicCellStorable};
+use std::{
+    fmt::{Debug, Formatter},
+    ptr::NonNull,
+};
+
+/// An atomically swappable raw pointer with no ownership semantics, for FFI handles, arena
+/// pointers and other values whose lifetime is managed outside the cell.
+///
+/// The pointer goes through the same take/put protocol as every other [`AtomicCell`], but the
+/// cell never frees what it points to and dropping it leaves the pointee alone. Like
+/// [`AtomicPtr`](std::sync::atomic::AtomicPtr) it is `Send` and `Sync` for any `T`, since
+/// dereferencing the pointer is already `unsafe` and it is the caller's job to make sure that
+/// is valid on whichever thread does it.
+///
+/// The address `usize::MAX` is reserved for the cell's taken sentinel, so storing a pointer to
+/// it panics. No allocation or properly aligned pointer can end up there, but an FFI handle
+/// like Windows' `INVALID_HANDLE_VALUE` can, so map those to `None` first.
+///
+/// ```
+/// use arc_cell::RawPtrCell;
+/// use std::ptr::NonNull;
+///
+/// static HANDLE: RawPtrCell<u32> = RawPtrCell::null();
+///
+/// let value = Box::leak(Box::new(5));
+/// HANDLE.store(Some(NonNull::from(&mut *value)));
+/// assert_eq!(unsafe { HANDLE.load_ref() }, Some(&5));
+///
+/// let ptr = HANDLE.take().unwrap();
+/// drop(unsafe { Box::from_raw(ptr.as_ptr()) });
+/// ```
+pub struct RawPtrCell<T> {
+    cell: AtomicCell<Option<NonNull<T>>>,
+}
+
+unsafe impl<T> Send for RawPtrCell<T> {}
+unsafe impl<T> Sync for RawPtrCell<T> {}
+
+impl<T> RawPtrCell<T> {
+    /// Create a new RawPtrCell holding `ptr`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` points to the address `usize::MAX`.
+    pub fn new(ptr: Option<NonNull<T>>) -> Self {
+        RawPtrCell {
+            cell: AtomicCell::new(check(ptr)),
+        }
+    }
+
+    /// Create a new empty RawPtrCell, usable in statics.
+    pub const fn null() -> Self {
+        RawPtrCell {
+            cell: AtomicCell::const_new(),
+        }
+    }
+
+    /// Returns the pointer currently in the cell.
+    pub fn load(&self) -> Option<NonNull<T>> {
+        self.cell.get()
+    }
+
+    /// Replace the pointer in the cell, returning the old pointer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` points to the address `usize::MAX`.
+    pub fn store(&self, ptr: Option<NonNull<T>>) -> Option<NonNull<T>> {
+        self.cell.set(check(ptr))
+    }
+
+    /// Takes the pointer out of the cell, leaving it empty.
+    pub fn take(&self) -> Option<NonNull<T>> {
+        self.cell.set(None)
+    }
+
+    /// Stores `new` into the cell if it currently holds `current`, see
+    /// [`AtomicCell::compare_exchange`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `new` points to the address `usize::MAX`.
+    pub fn compare_exchange(
+        &self,
+        current: Option<NonNull<T>>,
+        new: Option<NonNull<T>>,
+    ) -> Result<Option<NonNull<T>>, Option<NonNull<T>>> {
+        self.cell.compare_exchange(&current, check(new))
+    }
+
+    /// Returns a reference to the value the cell currently points to, if any.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be valid for reads and properly aligned, and the pointee must stay
+    /// alive and unmutated for the whole lifetime `'a`, as with [`NonNull::as_ref`].
+    pub unsafe fn load_ref<'a>(&self) -> Option<&'a T> {
+        self.load().map(|ptr| &*ptr.as_ptr())
+    }
+
+    /// Consumes the cell, returning the pointer it holds.
+    pub fn into_inner(self) -> Option<NonNull<T>> {
+        self.cell.into_inner()
+    }
+}
+
+/// Rejects the one address a raw pointer can't be stored as.
+fn check<T>(ptr: Option<NonNull<T>>) -> Option<NonNull<T>> {
+    if let Some(ptr) = ptr {
+        assert_ne!(
+            ptr.as_ptr() as usize,
+            <NonNull<T> as AtomicCellStorable>::TAKEN_VALUE,
+            "can't store a pointer to the address usize::MAX in a cell"
+        );
+    }
+    ptr
+}
+
+impl<T> Default for RawPtrCell<T> {
+    fn default() -> Self {
+        RawPtrCell::null()
+    }
+}
+
+impl<T> Debug for RawPtrCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("RawPtrCell").field(&self.load()).finish()
+    }
+}
+
+// Pointers to the address `usize::MAX` would collide with the sentinel, so converting one
+// panics. `RawPtrCell` rules them out before touching the cell, since some methods convert the
+// new value while the old one is taken and a panic there would leave the cell taken.
+unsafe impl<T> AtomicCellStorable for NonNull<T> {
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        check(Some(self));
+        self.as_ptr() as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        NonNull::new_unchecked(value as *mut T)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<NonNull<T>> {
+    const TAKEN_VALUE: usize = <NonNull<T> as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match check(self) {
+            None => crate::EMPTY_OPTION,
+            Some(ptr) => ptr.as_ptr() as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        NonNull::new(value as *mut T)
+    }
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<NonNull<T>> {
+    const DEFAULT_VALUE: usize = crate::EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AtomicCell, RawPtrCell};
+    use std::{ptr::NonNull, sync::Arc};
+
+    #[test]
+    fn raw_ptr_cell() {
+        let mut arena = [1u32, 2, 3];
+        let first = std::ptr::NonNull::from(&mut arena[0]);
+        let second = std::ptr::NonNull::from(&mut arena[1]);
+        let cell = Arc::new(RawPtrCell::new(Some(first)));
+        let other = cell.clone();
+        std::thread::spawn(move || {
+            // Step to the next slot of the arena without touching what it points to.
+            let current = other.load();
+            let next = current.map(|ptr| unsafe { ptr.add(1) });
+            assert_eq!(other.compare_exchange(current, next), Ok(current));
+        })
+        .join()
+        .unwrap();
+        assert_eq!(cell.load(), Some(second));
+        assert_eq!(unsafe { cell.load_ref() }, Some(&2));
+        assert_eq!(cell.compare_exchange(Some(first), None), Err(None));
+        assert_eq!(cell.take(), Some(second));
+        assert!(cell.load().is_none());
+        assert_eq!(arena, [1, 2, 3]);
+    }
+
+    #[test]
+    fn reserved_address() {
+        let reserved = NonNull::new(usize::MAX as *mut u8).unwrap();
+        let cell = RawPtrCell::null();
+        let result = std::panic::catch_unwind(|| cell.store(Some(reserved)));
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(|| cell.compare_exchange(None, Some(reserved)));
+        assert!(result.is_err());
+        assert!(cell.load().is_none());
+        assert!(std::panic::catch_unwind(|| RawPtrCell::new(Some(reserved))).is_err());
+        assert!(std::panic::catch_unwind(|| AtomicCell::new(reserved)).is_err());
+        assert!(std::panic::catch_unwind(|| AtomicCell::new(Some(reserved))).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+use std::{
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicPtr, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+/// An Arc cell for hot, read-mostly data, whose [`get`](Self::get) is wait-free.
+///
+/// [`AtomicCell`](crate::AtomicCell) takes the value out of the cell for every access, so
+/// concurrent readers line up behind each other. Readers of a `ReadMostlyCell` never do that.
+/// They announce themselves in one of two sets of reader counts, load the pointer and bump its
+/// strong count, which is a fixed number of steps no matter what other threads are doing.
+///
+/// Writers pay for this instead. They are serialized by a lock, and after swapping in the new
+/// pointer each one waits for the readers that might still be looking at the old pointer
+/// before handing it back. Readers arriving after the swap are directed to the other set of
+/// counts, so a steady stream of readers can't starve a writer.
+///
+/// Each set is split into stripes on separate cache lines, and every thread always uses the
+/// same stripe, so readers on different threads mostly don't write to the same cache line.
+/// That makes each cell take a couple of kilobytes.
+///
+/// ```
+/// use arc_cell::ReadMostlyCell;
+/// use std::sync::Arc;
+///
+/// let routes = ReadMostlyCell::new(Arc::new(vec!["/"]));
+/// assert_eq!(*routes.get(), ["/"]);
+/// let old = routes.set(Arc::new(vec!["/", "/about"]));
+/// assert_eq!(*old, ["/"]);
+/// assert_eq!(routes.get().len(), 2);
+/// ```
+pub struct ReadMostlyCell<T> {
+    ptr: AtomicPtr<T>,
+    /// Which of the reader count sets new readers should use, only its lowest bit matters.
+    epoch: AtomicUsize,
+    readers: [[Stripe; STRIPES]; 2],
+    writer: Mutex<()>,
+    _marker: PhantomData<Arc<T>>,
+}
+
+impl<T> ReadMostlyCell<T> {
+    /// Create a new ReadMostlyCell holding `value`.
+    pub fn new(value: Arc<T>) -> Self {
+        ReadMostlyCell {
+            ptr: AtomicPtr::new(Arc::into_raw(value) as *mut T),
+            epoch: AtomicUsize::new(0),
+            readers: Default::default(),
+            writer: Mutex::new(()),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a copy of the Arc in the cell, without waiting for any other thread.
+    pub fn get(&self) -> Arc<T> {
+        LoadGuard::to_arc(&self.load_guard())
+    }
+
+    /// Borrows the value in the cell without touching the Arc's strong count, for short
+    /// critical sections on hot data where even the reference count is contended.
+    ///
+    /// Like [`get`](Self::get) this never waits, and since each thread counts itself in its own
+    /// stripe of the reader counts, readers on different threads don't write to any shared
+    /// cache line. While the guard is alive writers can still
+    /// swap in a new value, but they wait for the guard to be dropped before returning the old
+    /// one, so don't hold it for long. Writing to the cell from the thread holding the guard
+    /// deadlocks.
+    ///
+    /// ```
+    /// use arc_cell::ReadMostlyCell;
+    /// use std::sync::Arc;
+    ///
+    /// let cell = ReadMostlyCell::new(Arc::new(String::from("config")));
+    /// assert_eq!(cell.load_guard().len(), 6);
+    /// ```
+    pub fn load_guard(&self) -> LoadGuard<'_, T> {
+        let readers = &self.readers[self.epoch.load(Ordering::SeqCst) & 1][stripe()].0;
+        readers.fetch_add(1, Ordering::SeqCst);
+        let ptr = self.ptr.load(Ordering::SeqCst);
+        LoadGuard { ptr, readers }
+    }
+
+    /// Replace the Arc in the cell, returning the old one.
+    ///
+    /// This waits for readers that are in the middle of copying the old Arc.
+    pub fn set(&self, value: Arc<T>) -> Arc<T> {
+        let _writer = self.lock();
+        let old = self
+            .ptr
+            .swap(Arc::into_raw(value) as *mut T, Ordering::SeqCst);
+        self.wait_for_readers();
+        unsafe { Arc::from_raw(old) }
+    }
+
+    /// Stores `new` into the cell if it currently holds the same allocation as `current`,
+    /// returning the old Arc on success and handing `new` back otherwise.
+    pub fn compare_exchange(&self, current: &Arc<T>, new: Arc<T>) -> Result<Arc<T>, Arc<T>> {
+        let _writer = self.lock();
+        let current = Arc::as_ptr(current) as *mut T;
+        let new_ptr = Arc::into_raw(new) as *mut T;
+        match self
+            .ptr
+            .compare_exchange(current, new_ptr, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            Ok(old) => {
+                self.wait_for_readers();
+                Ok(unsafe { Arc::from_raw(old) })
+            }
+            Err(_) => Err(unsafe { Arc::from_raw(new_ptr) }),
+        }
+    }
+
+    /// Consumes the cell, returning the Arc it holds.
+    pub fn into_inner(self) -> Arc<T> {
+        let ptr = self.ptr.load(Ordering::SeqCst);
+        std::mem::forget(self);
+        unsafe { Arc::from_raw(ptr) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ()> {
+        // The lock doesn't protect any data, so poisoning can be ignored.
+        self.writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Waits until no reader that started before the last swap is still inside `get` or holding
+    /// a [`LoadGuard`].
+    fn wait_for_readers(&self) {
+        // Flipping the epoch first means only readers that already picked a count can keep
+        // it busy. Both counts are drained so readers that picked one before an earlier
+        // writer's flip are covered too.
+        for _ in 0..2 {
+            let previous = self.epoch.fetch_add(1, Ordering::SeqCst) & 1;
+            for stripe in &self.readers[previous] {
+                while stripe.0.load(Ordering::SeqCst) != 0 {
+                    std::thread::yield_now();
+                }
+            }
+        }
+    }
+}
+
+/// How many reader counts each set is split into. Threads beyond this share stripes, which is
+/// still correct, they just contend on the count again.
+const STRIPES: usize = 8;
+
+/// A reader count on its own cache line. Some CPUs prefetch cache lines in pairs, so this is
+/// twice the usual line size.
+#[derive(Default)]
+#[repr(align(128))]
+struct Stripe(AtomicUsize);
+
+/// Returns the stripe the current thread counts itself in.
+fn stripe() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static STRIPE: usize = NEXT.fetch_add(1, Ordering::Relaxed) % STRIPES;
+    }
+    // Threads being torn down fall back to the first stripe.
+    STRIPE.try_with(|stripe| *stripe).unwrap_or(0)
+}
+
+/// A borrow of the value in a [`ReadMostlyCell`], see [`ReadMostlyCell::load_guard`].
+pub struct LoadGuard<'a, T> {
+    ptr: *const T,
+    readers: &'a AtomicUsize,
+}
+
+// The guard only hands out `&T`, like a `&Arc<T>` would.
+unsafe impl<T: Send + Sync> Send for LoadGuard<'_, T> {}
+unsafe impl<T: Send + Sync> Sync for LoadGuard<'_, T> {}
+
+impl<T> LoadGuard<'_, T> {
+    /// Returns a new Arc pointing to the borrowed value.
+    pub fn to_arc(this: &Self) -> Arc<T> {
+        // Writers don't release the pointer until every reader that could have loaded it has
+        // left, so it is still alive while the guard is.
+        unsafe {
+            Arc::increment_strong_count(this.ptr);
+            Arc::from_raw(this.ptr)
+        }
+    }
+}
+
+impl<T> Deref for LoadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T> Drop for LoadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.readers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<T: Debug> Debug for LoadGuard<'_, T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        Debug::fmt(&**self, fmt)
+    }
+}
+
+impl<T> Drop for ReadMostlyCell<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Arc::from_raw(*self.ptr.get_mut())) }
+    }
+}
+
+impl<T: Default> Default for ReadMostlyCell<T> {
+    fn default() -> Self {
+        ReadMostlyCell::new(Arc::default())
+    }
+}
+
+impl<T> From<Arc<T>> for ReadMostlyCell<T> {
+    fn from(value: Arc<T>) -> Self {
+        ReadMostlyCell::new(value)
+    }
+}
+
+impl<T: Debug> Debug for ReadMostlyCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("ReadMostlyCell")
+            .field(&self.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LoadGuard, ReadMostlyCell};
+    use std::sync::{atomic::Ordering, Arc};
+
+    #[test]
+    fn read_mostly_cell() {
+        struct Tracked(usize, Arc<std::sync::atomic::AtomicUsize>);
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                self.1.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let cell = Arc::new(ReadMostlyCell::new(Arc::new(Tracked(0, dropped.clone()))));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..10_000 {
+                        let value = cell.get().0;
+                        assert!(value >= last);
+                        last = value;
+                    }
+                })
+            })
+            .collect();
+        for i in 1..=1000 {
+            cell.set(Arc::new(Tracked(i, dropped.clone())));
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(dropped.load(Ordering::SeqCst), 1000);
+
+        let current = cell.get();
+        let stale = Arc::new(Tracked(0, dropped.clone()));
+        assert!(cell.compare_exchange(&stale, stale.clone()).is_err());
+        assert!(cell.compare_exchange(&current, stale).is_ok());
+        assert_eq!(cell.get().0, 0);
+        drop(current);
+        drop(Arc::try_unwrap(cell).ok().unwrap().into_inner());
+        assert_eq!(dropped.load(Ordering::SeqCst), 1002);
+    }
+
+    #[test]
+    fn load_guard() {
+        let first = Arc::new(vec![1, 2]);
+        let cell = Arc::new(ReadMostlyCell::new(first.clone()));
+        let guard = cell.load_guard();
+        assert_eq!(*guard, [1, 2]);
+        assert_eq!(Arc::strong_count(&first), 2);
+
+        // The writer swaps immediately but has to wait for the guard before returning.
+        let writer = {
+            let cell = cell.clone();
+            std::thread::spawn(move || cell.set(Arc::new(vec![3])))
+        };
+        while cell.load_guard()[0] != 3 {
+            std::thread::yield_now();
+        }
+        assert!(!writer.is_finished());
+        let copy = LoadGuard::to_arc(&guard);
+        assert!(Arc::ptr_eq(&copy, &first));
+        drop(guard);
+        assert!(Arc::ptr_eq(&writer.join().unwrap(), &first));
+        assert_eq!(format!("{:?}", cell.load_guard()), "[3]");
+    }
+
+    #[test]
+    fn shared_stripes() {
+        // More threads than stripes, so some of them share a count.
+        let cell = Arc::new(ReadMostlyCell::new(Arc::new(0)));
+        let barrier = Arc::new(std::sync::Barrier::new(21));
+        let readers: Vec<_> = (0..20)
+            .map(|_| {
+                let (cell, barrier) = (cell.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    let guard = cell.load_guard();
+                    barrier.wait();
+                    barrier.wait();
+                    *guard
+                })
+            })
+            .collect();
+        barrier.wait();
+        let writer = {
+            let cell = cell.clone();
+            std::thread::spawn(move || cell.set(Arc::new(1)))
+        };
+        while *cell.get() != 1 {
+            std::thread::yield_now();
+        }
+        assert!(!writer.is_finished());
+        barrier.wait();
+        for reader in readers {
+            assert_eq!(reader.join().unwrap(), 0);
+        }
+        assert_eq!(*writer.join().unwrap(), 0);
+    }
+}
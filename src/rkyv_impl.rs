@@ -0,0 +1,95 @@
+//! `rkyv` support, archiving an [`ArcCell`] transparently as a snapshot of its current value.
+
+use crate::ArcCell;
+use rkyv::{
+    bytecheck::CheckBytes, rancor::Fallible, Archive, Deserialize, Place, Portable, Serialize,
+};
+use std::{ops::Deref, sync::Arc};
+
+/// The archived form of an [`ArcCell<T>`](crate::ArcCell), holding `T::Archived`.
+#[repr(transparent)]
+pub struct ArchivedArcCell<A>(A);
+
+impl<A> ArchivedArcCell<A> {
+    /// Returns the archived value.
+    pub fn get(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A> Deref for ArchivedArcCell<A> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        &self.0
+    }
+}
+
+// SAFETY: `ArchivedArcCell<A>` is a transparent wrapper around `A`.
+unsafe impl<A: Portable> Portable for ArchivedArcCell<A> {}
+
+// SAFETY: `ArchivedArcCell<A>` is a transparent wrapper around `A`, so it has the same bit
+// validity.
+unsafe impl<A: CheckBytes<C>, C: Fallible + ?Sized> CheckBytes<C> for ArchivedArcCell<A> {
+    unsafe fn check_bytes(value: *const Self, context: &mut C) -> Result<(), C::Error> {
+        A::check_bytes(value.cast::<A>(), context)
+    }
+}
+
+impl<T: Archive> Archive for ArcCell<T> {
+    type Archived = ArchivedArcCell<T::Archived>;
+    /// Keeps the value that was serialized, since the cell may have changed by the time it's
+    /// resolved.
+    type Resolver = (Arc<T>, T::Resolver);
+
+    fn resolve(&self, (value, resolver): Self::Resolver, out: Place<Self::Archived>) {
+        let out = unsafe { out.cast_unchecked::<T::Archived>() };
+        T::resolve(&value, resolver, out);
+    }
+}
+
+impl<T: Serialize<S>, S: Fallible + ?Sized> Serialize<S> for ArcCell<T> {
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let value = self.get();
+        let resolver = T::serialize(&value, serializer)?;
+        Ok((value, resolver))
+    }
+}
+
+impl<T, D> Deserialize<ArcCell<T>, D> for ArchivedArcCell<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<ArcCell<T>, D::Error> {
+        self.0
+            .deserialize(deserializer)
+            .map(|value| ArcCell::new(Arc::new(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn rkyv() {
+        #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+        struct State {
+            name: ArcCell<String>,
+            count: u32,
+        }
+        let state = State {
+            name: ArcCell::new(Arc::new("a".into())),
+            count: 3,
+        };
+        let bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&state).unwrap();
+        let archived = rkyv::access::<ArchivedState, rkyv::rancor::Error>(&bytes).unwrap();
+        assert_eq!(archived.name.as_str(), "a");
+        let state: State = rkyv::deserialize::<_, rkyv::rancor::Error>(archived).unwrap();
+        assert_eq!(*state.name.get(), "a");
+        assert_eq!(state.count, 3);
+    }
+}
@@ -0,0 +1,62 @@
+//! `schemars` support, describing cells with the schema of the value they hold.
+
+use crate::{ArcCell, OptionalArcCell};
+use schemars::{JsonSchema, Schema, SchemaGenerator};
+use std::borrow::Cow;
+
+impl<T: JsonSchema> JsonSchema for ArcCell<T> {
+    fn inline_schema() -> bool {
+        T::inline_schema()
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        T::schema_name()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        T::schema_id()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        T::json_schema(generator)
+    }
+}
+
+/// Reports the nullable schema of `Option<T>`.
+///
+/// Unlike a plain `Option` field, a field holding a cell is still required to be present.
+impl<T: JsonSchema> JsonSchema for OptionalArcCell<T> {
+    fn inline_schema() -> bool {
+        Option::<T>::inline_schema()
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        Option::<T>::schema_name()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Option::<T>::schema_id()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        Option::<T>::json_schema(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, OptionalArcCell};
+
+    #[test]
+    fn schemars() {
+        let mut generator = schemars::SchemaGenerator::default();
+        assert_eq!(
+            generator.subschema_for::<ArcCell<u32>>(),
+            generator.subschema_for::<u32>()
+        );
+        assert_eq!(
+            generator.subschema_for::<OptionalArcCell<String>>(),
+            generator.subschema_for::<Option<String>>()
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! `serde` support, serializing cells transparently as a snapshot of their current value.
+
+use crate::{AtomicCell, AtomicCellStorable};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Arc;
+
+impl<T: Serialize + ?Sized> Serialize for AtomicCell<Arc<T>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for AtomicCell<Option<Arc<T>>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().as_deref().serialize(serializer)
+    }
+}
+
+impl<'de, T: AtomicCellStorable + Deserialize<'de>> Deserialize<'de> for AtomicCell<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(AtomicCell::new)
+    }
+}
+
+impl<T: AtomicCellStorable> AtomicCell<T> {
+    /// Deserializes a new value and replaces the value in the cell with it, returning the old
+    /// value.
+    ///
+    /// The cell is only touched once deserialization has succeeded, so on error it keeps its
+    /// old value.
+    pub fn set_from<'de, D>(&self, deserializer: D) -> Result<T, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(|value| self.set(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCell, OptionalArcCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn serialize() {
+        #[derive(serde::Serialize)]
+        struct State {
+            name: ArcCell<String>,
+            limit: OptionalArcCell<u32>,
+        }
+        let state = State {
+            name: ArcCell::new(Arc::new("a".into())),
+            limit: OptionalArcCell::new(None),
+        };
+        let json = || serde_json::to_string(&state).unwrap();
+        assert_eq!(json(), r#"{"name":"a","limit":null}"#);
+        state.limit.set(Some(Arc::new(5)));
+        assert_eq!(json(), r#"{"name":"a","limit":5}"#);
+    }
+
+    #[test]
+    fn deserialize() {
+        #[derive(serde::Deserialize)]
+        struct State {
+            name: ArcCell<String>,
+            limit: OptionalArcCell<u32>,
+        }
+        let state: State = serde_json::from_str(r#"{"name":"a","limit":null}"#).unwrap();
+        assert_eq!(*state.name.get(), "a");
+        assert_eq!(state.limit.get(), None);
+
+        let mut json = serde_json::Deserializer::from_str("5");
+        assert_eq!(state.limit.set_from(&mut json).unwrap(), None);
+        assert_eq!(state.limit.get(), Some(Arc::new(5)));
+        let mut json = serde_json::Deserializer::from_str("true");
+        assert!(state.limit.set_from(&mut json).is_err());
+        assert_eq!(state.limit.get(), Some(Arc::new(5)));
+    }
+
+    #[test]
+    fn serde_str() {
+        let name: ArcCell<str> = serde_json::from_str(r#""a""#).unwrap();
+        assert_eq!(serde_json::to_string(&name).unwrap(), r#""a""#);
+    }
+}
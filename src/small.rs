@@ -0,0 +1,258 @@
+use crate::{AtomicCell, AtomicCellStorable};
+use std::{
+    fmt::{Debug, Formatter},
+    mem::{size_of, ManuallyDrop, MaybeUninit},
+    num::{NonZeroU16, NonZeroU32, NonZeroU8},
+    sync::Arc,
+};
+
+/// Types whose values have every byte initialized, so they can be copied into a [`SmallCell`]
+/// byte for byte.
+///
+/// # Safety
+///
+/// The type must not contain any padding, and every one of its bytes must be initialized in
+/// every value. That rules out structs with padding between or after their fields, unions, and
+/// enums with fields, since the bytes of the fields of one variant are uninitialized while
+/// another variant is stored. Fieldless enums with a primitive representation like
+/// `#[repr(u8)]` are fine.
+///
+/// ```
+/// use arc_cell::{NoUninit, SmallCell};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// #[repr(u8)]
+/// enum Mode {
+///     Normal,
+///     Maintenance,
+/// }
+///
+/// unsafe impl NoUninit for Mode {}
+///
+/// let mode = SmallCell::new(Mode::Normal);
+/// assert_eq!(mode.set(Mode::Maintenance), Mode::Normal);
+/// ```
+pub unsafe trait NoUninit: Copy + 'static {}
+
+macro_rules! no_uninit {
+    ($($ty:ty),*) => {
+        $(unsafe impl NoUninit for $ty {})*
+    };
+}
+
+no_uninit!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    f32,
+    f64,
+    NonZeroU8,
+    NonZeroU16,
+    NonZeroU32
+);
+
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+
+/// An atomically swappable value that is stored inline when it fits, and in an Arc otherwise.
+///
+/// Values of types smaller than a `usize`, like most fieldless enums and integers up to `u32`,
+/// have their bytes stored directly in the cell without any allocation, leaving at least one
+/// byte zeroed so no value can collide with the cell's taken sentinel. That is only sound if all
+/// of their bytes are initialized, which [`NoUninit`] promises. Larger types fall back to
+/// `Arc<T>`. Which representation is used depends only on `T`, see
+/// [`is_inline`](Self::is_inline).
+///
+/// ```
+/// use arc_cell::SmallCell;
+///
+/// let cell = SmallCell::new(7u32);
+/// assert!(SmallCell::<u32>::is_inline());
+/// assert_eq!(cell.set(8), 7);
+/// assert_eq!(cell.get(), 8);
+///
+/// let big = SmallCell::new([1u64; 4]);
+/// assert!(!SmallCell::<[u64; 4]>::is_inline());
+/// assert_eq!(big.take(), [1; 4]);
+/// ```
+pub struct SmallCell<T: NoUninit> {
+    cell: AtomicCell<Small<T>>,
+}
+
+impl<T: NoUninit> SmallCell<T> {
+    /// Create a new SmallCell holding `value`.
+    pub fn new(value: T) -> Self {
+        SmallCell {
+            cell: AtomicCell::new(Small::new(value)),
+        }
+    }
+
+    /// Returns whether values of `T` are stored inline rather than in an Arc.
+    pub const fn is_inline() -> bool {
+        size_of::<T>() < size_of::<usize>()
+    }
+
+    /// Returns a copy of the value in the cell.
+    pub fn get(&self) -> T {
+        self.cell.get().into_inner()
+    }
+
+    /// Replace the value in the cell, returning the old value.
+    pub fn set(&self, value: T) -> T {
+        self.cell.set(Small::new(value)).into_inner()
+    }
+
+    /// Consumes the cell, returning the value it holds.
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner().into_inner()
+    }
+}
+
+impl<T: NoUninit + Default> SmallCell<T> {
+    /// Takes the value out of the cell, leaving the default value in its place.
+    pub fn take(&self) -> T {
+        self.set(T::default())
+    }
+}
+
+impl<T: NoUninit + Default> Default for SmallCell<T> {
+    fn default() -> Self {
+        SmallCell::new(T::default())
+    }
+}
+
+impl<T: NoUninit> From<T> for SmallCell<T> {
+    fn from(value: T) -> Self {
+        SmallCell::new(value)
+    }
+}
+
+impl<T: NoUninit + Debug> Debug for SmallCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_tuple("SmallCell").field(&self.get()).finish()
+    }
+}
+
+/// The value stored in a [`SmallCell`], always `Inline` if `SmallCell::<T>::is_inline()` and
+/// `Shared` otherwise.
+#[derive(Clone)]
+enum Small<T: NoUninit> {
+    Inline(T),
+    Shared(Arc<T>),
+}
+
+impl<T: NoUninit> Small<T> {
+    fn new(value: T) -> Self {
+        if SmallCell::<T>::is_inline() {
+            Small::Inline(value)
+        } else {
+            Small::Shared(Arc::new(value))
+        }
+    }
+
+    fn into_inner(self) -> T {
+        match self {
+            Small::Inline(value) => value,
+            Small::Shared(arc) => *arc,
+        }
+    }
+}
+
+unsafe impl<T: NoUninit> AtomicCellStorable for Small<T> {
+    // Inline values always leave the last byte zeroed, and Arc pointers are never MAX.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        match self {
+            Small::Inline(value) => {
+                let value = ManuallyDrop::new(value);
+                let mut raw = 0usize;
+                // `NoUninit` guarantees every byte copied here is initialized.
+                unsafe {
+                    std::ptr::copy_nonoverlapping(
+                        &*value as *const T as *const u8,
+                        &mut raw as *mut usize as *mut u8,
+                        size_of::<T>(),
+                    );
+                }
+                raw
+            }
+            Small::Shared(arc) => Arc::into_raw(arc) as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        if SmallCell::<T>::is_inline() {
+            let mut inline = MaybeUninit::<T>::uninit();
+            std::ptr::copy_nonoverlapping(
+                &value as *const usize as *const u8,
+                inline.as_mut_ptr() as *mut u8,
+                size_of::<T>(),
+            );
+            Small::Inline(inline.assume_init())
+        } else {
+            Small::Shared(Arc::from_raw(value as *const T))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NoUninit, SmallCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn small_cell() {
+        #[derive(Clone, Copy, Debug, Default, PartialEq)]
+        #[repr(u16)]
+        enum Phase {
+            #[default]
+            Idle,
+            Running0,
+            Running1,
+            Running2,
+            Running3,
+        }
+        unsafe impl NoUninit for Phase {}
+        const RUNNING: [Phase; 4] = [
+            Phase::Running0,
+            Phase::Running1,
+            Phase::Running2,
+            Phase::Running3,
+        ];
+
+        assert!(SmallCell::<Phase>::is_inline());
+        let cell = Arc::new(SmallCell::new(Phase::Idle));
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let cell = cell.clone();
+                std::thread::spawn(move || cell.set(RUNNING[i]))
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert!(RUNNING.contains(&cell.take()));
+        assert_eq!(cell.get(), Phase::Idle);
+        assert_eq!(SmallCell::new(()).into_inner(), ());
+
+        assert!(!SmallCell::<[u64; 2]>::is_inline());
+        let large = SmallCell::new([1u64, 2]);
+        let copy = large.get();
+        assert_eq!(large.set([3, 4]), copy);
+        assert_eq!(format!("{:?}", large), "SmallCell([3, 4])");
+        assert_eq!(
+            SmallCell::new([true, false, true]).into_inner(),
+            [true, false, true]
+        );
+    }
+}
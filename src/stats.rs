@@ -0,0 +1,66 @@
+//! Per-cell access counters for the `stats` feature.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of how a cell has been accessed, see [`AtomicCell::stats`](crate::AtomicCell::stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CellStats {
+    /// The number of times the value was read without being modified.
+    pub gets: u64,
+    /// The number of times the value was modified.
+    pub sets: u64,
+    /// The number of times a thread had to retry because another thread was accessing the cell.
+    pub retries: u64,
+}
+
+pub(crate) struct Counters {
+    gets: AtomicU64,
+    sets: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) const fn new() -> Self {
+        Counters {
+            gets: AtomicU64::new(0),
+            sets: AtomicU64::new(0),
+            retries: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn get(&self) {
+        self.gets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set(&self) {
+        self.sets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CellStats {
+        CellStats {
+            gets: self.gets.load(Ordering::Relaxed),
+            sets: self.sets.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCell;
+    use std::sync::Arc;
+
+    #[test]
+    fn stats() {
+        let cell = ArcCell::new(Arc::new(1));
+        cell.get();
+        cell.get();
+        cell.set(Arc::new(2));
+        let stats = cell.stats();
+        assert_eq!((stats.gets, stats.sets, stats.retries), (2, 1, 0));
+    }
+}
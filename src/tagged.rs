@@ -0,0 +1,162 @@
+use crate::{AtomicCell, AtomicCellStorable};
+use std::{
+    fmt::{Debug, Formatter},
+    mem::align_of,
+    sync::Arc,
+};
+
+/// An Arc along with a tag of `BITS` bits, stored together in a
+/// [`TaggedArcCell`](crate::TaggedArcCell) by packing the tag into the pointer's alignment bits.
+///
+/// Arc pointers are aligned to at least a `usize`, so up to 2 bits are always available on
+/// 32-bit targets and 3 bits on 64-bit targets. Using more bits than `T`'s alignment allows
+/// fails to compile.
+pub struct TaggedArc<T, const BITS: usize> {
+    arc: Arc<T>,
+    tag: usize,
+}
+
+impl<T, const BITS: usize> TaggedArc<T, BITS> {
+    /// The bits of the raw value that hold the tag.
+    const MASK: usize = {
+        let align = if align_of::<T>() > align_of::<usize>() {
+            align_of::<T>()
+        } else {
+            align_of::<usize>()
+        };
+        assert!(1 << BITS <= align, "not enough alignment bits for the tag");
+        (1 << BITS) - 1
+    };
+
+    /// Pairs `arc` with `tag`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` doesn't fit in `BITS` bits.
+    pub fn new(arc: Arc<T>, tag: usize) -> Self {
+        assert!(tag & !Self::MASK == 0, "tag doesn't fit in {} bits", BITS);
+        TaggedArc { arc, tag }
+    }
+
+    /// Returns the Arc.
+    pub fn arc(&self) -> &Arc<T> {
+        &self.arc
+    }
+
+    /// Returns the tag.
+    pub fn tag(&self) -> usize {
+        self.tag
+    }
+
+    /// Returns the Arc and the tag.
+    pub fn into_parts(self) -> (Arc<T>, usize) {
+        (self.arc, self.tag)
+    }
+}
+
+impl<T, const BITS: usize> Clone for TaggedArc<T, BITS> {
+    fn clone(&self) -> Self {
+        TaggedArc {
+            arc: self.arc.clone(),
+            tag: self.tag,
+        }
+    }
+}
+
+impl<T: Debug, const BITS: usize> Debug for TaggedArc<T, BITS> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("TaggedArc")
+            .field("arc", &self.arc)
+            .field("tag", &self.tag)
+            .finish()
+    }
+}
+
+unsafe impl<T, const BITS: usize> AtomicCellStorable for TaggedArc<T, BITS> {
+    // The pointer bits of this are never a valid Arc, whatever the tag.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        Arc::into_raw(self.arc) as usize | self.tag
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        TaggedArc {
+            arc: Arc::from_raw((value & !Self::MASK) as *const T),
+            tag: value & Self::MASK,
+        }
+    }
+}
+
+impl<T, const BITS: usize> AtomicCell<TaggedArc<T, BITS>> {
+    /// Returns a clone of the stored Arc.
+    pub fn get_arc(&self) -> Arc<T> {
+        self.with(|value| value.arc.clone())
+    }
+
+    /// Returns the current tag.
+    ///
+    /// This does not take the value out of the cell or touch any reference counts.
+    pub fn get_tag(&self) -> usize {
+        self.internal_load() & TaggedArc::<T, BITS>::MASK
+    }
+
+    /// Replaces the tag, keeping the same Arc, and returns the old tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` doesn't fit in `BITS` bits.
+    pub fn set_tag(&self, tag: usize) -> usize {
+        // Check this up front so we can't panic while the value is taken.
+        let mask = TaggedArc::<T, BITS>::MASK;
+        assert!(tag & !mask == 0, "tag doesn't fit in {} bits", BITS);
+        let mut value = self.internal_lock();
+        std::mem::replace(&mut value.tag, tag)
+    }
+
+    /// Replaces both the Arc and the tag, returning the old ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` doesn't fit in `BITS` bits.
+    pub fn swap_tagged(&self, arc: Arc<T>, tag: usize) -> (Arc<T>, usize) {
+        self.set(TaggedArc::new(arc, tag)).into_parts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TaggedArc, TaggedArcCell};
+    use std::sync::Arc;
+
+    #[test]
+    fn tagged_arc_cell() {
+        const DIRTY: usize = 1;
+        let data = Arc::new(5);
+        let cell: TaggedArcCell<i32, 2> = TaggedArcCell::new(TaggedArc::new(data.clone(), 0));
+        assert_eq!(cell.set_tag(DIRTY), 0);
+        assert_eq!(cell.get_tag(), DIRTY);
+        assert!(Arc::ptr_eq(&cell.get_arc(), &data));
+
+        let current = cell.get();
+        assert_eq!(
+            cell.compare_exchange(&current, TaggedArc::new(Arc::new(6), 3))
+                .unwrap()
+                .tag(),
+            DIRTY
+        );
+        assert!(cell
+            .compare_exchange(&current, TaggedArc::new(data, 0))
+            .is_err());
+        let (arc, tag) = cell.swap_tagged(Arc::new(7), 2);
+        assert_eq!((*arc, tag), (6, 3));
+        assert_eq!(cell.get().tag(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn tagged_arc_cell_overflow() {
+        let cell: TaggedArcCell<i32, 1> = TaggedArcCell::new(TaggedArc::new(Arc::new(5), 0));
+        cell.set_tag(2);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::{AtomicCellConstInit, AtomicCellStorable, EMPTY_OPTION};
+use std::{
+    alloc::{self, Layout},
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+    ptr::{self, NonNull},
+    sync::atomic::{self, AtomicUsize, Ordering},
+};
+
+/// A reference counted header followed by a slice, behind a single thin pointer.
+///
+/// Unlike an `Arc<[T]>` this fits in a `usize`, so it can be stored in an
+/// [`AtomicCell`](crate::AtomicCell) directly. There are no weak references.
+pub struct ThinArc<H, T> {
+    ptr: NonNull<Inner<H>>,
+    _marker: PhantomData<(H, T)>,
+}
+
+/// The start of the allocation, the slice follows it.
+#[repr(C)]
+struct Inner<H> {
+    count: AtomicUsize,
+    len: usize,
+    header: H,
+}
+
+unsafe impl<H: Send + Sync, T: Send + Sync> Send for ThinArc<H, T> {}
+unsafe impl<H: Send + Sync, T: Send + Sync> Sync for ThinArc<H, T> {}
+
+impl<H, T> ThinArc<H, T> {
+    /// Create a new ThinArc holding `header` and the contents of `items`.
+    pub fn from_header_and_vec(header: H, mut items: Vec<T>) -> Self {
+        let len = items.len();
+        let (layout, offset) = Self::layout(len);
+        unsafe {
+            let ptr = alloc::alloc(layout) as *mut Inner<H>;
+            let ptr = match NonNull::new(ptr) {
+                Some(ptr) => ptr,
+                None => alloc::handle_alloc_error(layout),
+            };
+            ptr.as_ptr().write(Inner {
+                count: AtomicUsize::new(1),
+                len,
+                header,
+            });
+            let data = (ptr.as_ptr() as *mut u8).add(offset) as *mut T;
+            ptr::copy_nonoverlapping(items.as_ptr(), data, len);
+            // The items were moved into the allocation.
+            items.set_len(0);
+            ThinArc {
+                ptr,
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Create a new ThinArc holding `header` and clones of `items`.
+    pub fn from_header_and_slice(header: H, items: &[T]) -> Self
+    where
+        T: Clone,
+    {
+        ThinArc::from_header_and_vec(header, items.to_vec())
+    }
+
+    /// Returns the header.
+    pub fn header(&self) -> &H {
+        &self.inner().header
+    }
+
+    /// Returns the slice.
+    pub fn slice(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.data_ptr(), self.inner().len) }
+    }
+
+    /// Returns whether both point to the same allocation.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    /// Returns the number of references to the allocation.
+    pub fn strong_count(this: &Self) -> usize {
+        this.inner().count.load(Ordering::SeqCst)
+    }
+
+    fn inner(&self) -> &Inner<H> {
+        unsafe { self.ptr.as_ref() }
+    }
+
+    /// Returns a pointer to the first element of the slice, derived from the allocation's
+    /// pointer so it can also be used for writing.
+    fn data_ptr(&self) -> *mut T {
+        let (_, offset) = Self::layout(self.inner().len);
+        unsafe { (self.ptr.as_ptr() as *mut u8).add(offset) as *mut T }
+    }
+
+    /// Returns the layout of the allocation and the offset of the slice in it.
+    fn layout(len: usize) -> (Layout, usize) {
+        let (layout, offset) = Layout::new::<Inner<H>>()
+            .extend(Layout::array::<T>(len).expect("ThinArc is too large"))
+            .expect("ThinArc is too large");
+        (layout.pad_to_align(), offset)
+    }
+}
+
+impl<H, T> Clone for ThinArc<H, T> {
+    fn clone(&self) -> Self {
+        let old = self.inner().count.fetch_add(1, Ordering::Relaxed);
+        // Like Arc, guard against the count overflowing from leaked clones.
+        if old > isize::MAX as usize {
+            std::process::abort();
+        }
+        ThinArc {
+            ptr: self.ptr,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<H, T> Drop for ThinArc<H, T> {
+    fn drop(&mut self) {
+        if self.inner().count.fetch_sub(1, Ordering::Release) != 1 {
+            return;
+        }
+        atomic::fence(Ordering::Acquire);
+        let len = self.inner().len;
+        let (layout, _) = Self::layout(len);
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.data_ptr(), len));
+            ptr::drop_in_place(self.ptr.as_ptr());
+            alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout);
+        }
+    }
+}
+
+impl<H: Debug, T: Debug> Debug for ThinArc<H, T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("ThinArc")
+            .field("header", self.header())
+            .field("slice", &self.slice())
+            .finish()
+    }
+}
+
+unsafe impl<H, T> AtomicCellStorable for ThinArc<H, T> {
+    // The allocation is aligned to at least a `usize`, so this is never a valid pointer.
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        let this = std::mem::ManuallyDrop::new(self);
+        this.ptr.as_ptr() as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        ThinArc {
+            ptr: NonNull::new_unchecked(value as *mut Inner<H>),
+            _marker: PhantomData,
+        }
+    }
+}
+
+unsafe impl<H, T> AtomicCellStorable for Option<ThinArc<H, T>> {
+    const TAKEN_VALUE: usize = <ThinArc<H, T> as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(arc) => arc.into_value(),
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(ThinArc::from_value(value)),
+        }
+    }
+}
+
+unsafe impl<H, T> AtomicCellConstInit for Option<ThinArc<H, T>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AtomicCell, ThinArc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn thin_arc() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+        struct DropCount(u8);
+        impl std::ops::Drop for DropCount {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let items = vec![DropCount(1), DropCount(2)];
+        let cell = AtomicCell::new(ThinArc::from_header_and_vec(7u64, items));
+        let snapshot = cell.get();
+        assert_eq!(*snapshot.header(), 7);
+        assert_eq!(
+            snapshot
+                .slice()
+                .iter()
+                .map(|item| item.0)
+                .collect::<Vec<_>>(),
+            [1, 2]
+        );
+        assert_eq!(ThinArc::strong_count(&snapshot), 2);
+
+        cell.set(ThinArc::from_header_and_vec(8, Vec::new()));
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+        drop(snapshot);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 2);
+        assert!(cell.get().slice().is_empty());
+
+        let zst = ThinArc::from_header_and_slice((), &[(), ()]);
+        assert_eq!(zst.clone().slice().len(), 2);
+    }
+}
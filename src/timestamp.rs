@@ -0,0 +1,48 @@
+//! Cheap timestamps for the `last-modified` feature, stored as nanoseconds since a process-wide
+//! base instant so they fit in an `AtomicU64`.
+
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+static BASE: OnceLock<Instant> = OnceLock::new();
+
+fn base() -> Instant {
+    *BASE.get_or_init(Instant::now)
+}
+
+pub(crate) fn now() -> u64 {
+    base().elapsed().as_nanos() as u64
+}
+
+pub(crate) fn to_instant(nanos: u64) -> Instant {
+    base() + Duration::from_nanos(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCell;
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn last_modified() {
+        let cell = ArcCell::new(Arc::new(1));
+        let created = cell.last_modified();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(cell.age() >= Duration::from_millis(5));
+        cell.get();
+        assert_eq!(cell.last_modified(), created);
+        // Compare against instants taken around the store instead of a fixed bound, so a slow
+        // machine can't make this fail.
+        let before = Instant::now();
+        cell.set(Arc::new(2));
+        let after = Instant::now();
+        assert!(cell.last_modified() > created);
+        assert!(before <= cell.last_modified() && cell.last_modified() <= after);
+        assert!(cell.age() <= before.elapsed());
+    }
+}
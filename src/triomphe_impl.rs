@@ -0,0 +1,54 @@
+//! Storable impls for [`triomphe::Arc`].
+
+use crate::{AtomicCellConstInit, AtomicCellStorable, EMPTY_OPTION};
+use triomphe::Arc;
+
+unsafe impl<T> AtomicCellStorable for Arc<T> {
+    const TAKEN_VALUE: usize = usize::MAX;
+
+    fn into_value(self) -> usize {
+        Arc::into_raw(self) as usize
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        Arc::from_raw(value as *const T)
+    }
+}
+
+unsafe impl<T> AtomicCellStorable for Option<Arc<T>> {
+    const TAKEN_VALUE: usize = <Arc<T> as AtomicCellStorable>::TAKEN_VALUE;
+
+    fn into_value(self) -> usize {
+        match self {
+            None => EMPTY_OPTION,
+            Some(arc) => Arc::into_raw(arc) as usize,
+        }
+    }
+
+    unsafe fn from_value(value: usize) -> Self {
+        match value {
+            EMPTY_OPTION => None,
+            value => Some(Arc::from_raw(value as *const T)),
+        }
+    }
+}
+
+unsafe impl<T> AtomicCellConstInit for Option<Arc<T>> {
+    const DEFAULT_VALUE: usize = EMPTY_OPTION;
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[test]
+    fn triomphe() {
+        use crate::{OptionalTriompheCell, TriompheCell};
+
+        let cell = TriompheCell::new(triomphe::Arc::new(1));
+        assert_eq!(*cell.set(triomphe::Arc::new(2)), 1);
+        assert_eq!(*cell.get(), 2);
+        static OPTIONAL: OptionalTriompheCell<i32> = OptionalTriompheCell::const_new();
+        assert_eq!(OPTIONAL.set(Some(cell.get())), None);
+        assert_eq!(OPTIONAL.take_some().as_deref(), Some(&2));
+    }
+}
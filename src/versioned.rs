@@ -0,0 +1,156 @@
+use crate::{park, AtomicCell, AtomicCellStorable};
+use std::{
+    fmt::{Debug, Formatter},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// An [`AtomicCell`] which also keeps a version number that is incremented every time a new
+/// value is stored.
+///
+/// The version is only ever modified while the value is taken out of the cell, so the two are
+/// always consistent with each other without needing a double-width atomic.
+pub struct VersionedCell<T: AtomicCellStorable> {
+    cell: AtomicCell<T>,
+    version: AtomicU64,
+}
+
+impl<T: AtomicCellStorable> VersionedCell<T> {
+    /// Create a new VersionedCell with the given initial value, at version 0.
+    pub fn new(value: T) -> Self {
+        VersionedCell {
+            cell: AtomicCell::new(value),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Replace the value in the cell, returning the old value.
+    pub fn set(&self, value: T) -> T {
+        self.set_versioned(value).0
+    }
+
+    /// Replace the value in the cell, returning the old value and the new version.
+    pub fn set_versioned(&self, value: T) -> (T, u64) {
+        let old = self.cell.internal_take();
+        // Bump the version before putting the value back, so that anyone who sees the new
+        // value also sees the new version.
+        let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+        self.cell.internal_put(value);
+        (old, version)
+    }
+
+    /// Stores `new` if no other value has been stored since the cell was at `version`.
+    ///
+    /// Unlike [`AtomicCell::compare_exchange`], this detects a value being replaced and then
+    /// stored again in between (the ABA problem), since that still increments the version. On
+    /// success the previous value and the new version are returned, otherwise `new` is handed
+    /// back to the caller.
+    pub fn compare_exchange_version(&self, version: u64, new: T) -> Result<(T, u64), T> {
+        let old = self.cell.internal_take();
+        // The version only changes while the value is taken, so it can't change under us here.
+        if self.version() == version {
+            let version = self.version.fetch_add(1, Ordering::SeqCst) + 1;
+            self.cell.internal_put(new);
+            Ok((old, version))
+        } else {
+            self.cell.internal_restore(old);
+            Err(new)
+        }
+    }
+
+    /// Returns the current version.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether a new value has been stored since the cell was at `version`.
+    pub fn changed_since(&self, version: u64) -> bool {
+        self.version() != version
+    }
+
+    /// Blocks the current thread until the next time a value is stored, returning the new
+    /// version.
+    pub fn wait_for_change(&self) -> u64 {
+        self.wait_for_change_since(self.version())
+    }
+
+    /// Blocks the current thread until a new value has been stored since the cell was at
+    /// `version`, returning the new version.
+    ///
+    /// Returns immediately if that has already happened.
+    pub fn wait_for_change_since(&self, version: u64) -> u64 {
+        let mut current = version;
+        park::wait_until(&self.cell as *const AtomicCell<T> as usize, || {
+            current = self.version();
+            current != version
+        });
+        current
+    }
+
+    /// Consumes the cell, returning the stored value.
+    pub fn into_inner(self) -> T {
+        self.cell.into_inner()
+    }
+}
+
+impl<T: AtomicCellStorable + Clone> VersionedCell<T> {
+    /// Returns a clone of the stored value.
+    pub fn get(&self) -> T {
+        self.cell.get()
+    }
+
+    /// Returns a clone of the stored value along with the version it was stored at.
+    pub fn get_versioned(&self) -> (T, u64) {
+        let value = self.cell.internal_lock();
+        (T::clone(&value), self.version())
+    }
+}
+
+impl<T: AtomicCellStorable + Default> Default for VersionedCell<T> {
+    fn default() -> Self {
+        VersionedCell::new(T::default())
+    }
+}
+
+impl<T: AtomicCellStorable + Clone + Debug> Debug for VersionedCell<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("VersionedCell")
+            .field("value", &self.get())
+            .field("version", &self.version())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::VersionedCell;
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn versioned_cell() {
+        let cell = Arc::new(VersionedCell::new(Arc::new(0)));
+        let version = cell.version();
+        let waiter = {
+            let cell = cell.clone();
+            std::thread::spawn(move || cell.wait_for_change_since(version))
+        };
+        std::thread::sleep(Duration::from_millis(10));
+        cell.set(Arc::new(1));
+        assert_eq!(waiter.join().unwrap(), 1);
+        assert_eq!(cell.get_versioned(), (Arc::new(1), 1));
+        assert_eq!(cell.set_versioned(Arc::new(2)), (Arc::new(1), 2));
+
+        let data = cell.get();
+        cell.set(Arc::new(3));
+        cell.set(data);
+        assert_eq!(
+            cell.compare_exchange_version(2, Arc::new(4)),
+            Err(Arc::new(4))
+        );
+        assert_eq!(
+            cell.compare_exchange_version(4, Arc::new(4)),
+            Ok((Arc::new(2), 5))
+        );
+        assert!(cell.changed_since(version));
+        assert!(!cell.changed_since(cell.version()));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::OptionalArcCell;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+    task::Waker,
+};
+
+/// A slot holding the [`Waker`] of the task waiting on some event, like `AtomicWaker` from
+/// futures-util but built on [`OptionalArcCell`].
+///
+/// The waiting task calls [`register`](Self::register) before checking whether the event
+/// happened, and whoever makes it happen calls [`wake`](Self::wake) afterwards. Since both
+/// sides go through the same cell, a wake racing with a registration either wakes the new
+/// waker or happens early enough that the task sees the event when it checks.
+///
+/// ```
+/// use arc_cell::AtomicWakerCell;
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use std::task::{Context, Poll};
+///
+/// struct Flag {
+///     set: AtomicBool,
+///     waker: AtomicWakerCell,
+/// }
+///
+/// impl Future for &Flag {
+///     type Output = ();
+///
+///     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+///         self.waker.register(cx.waker());
+///         if self.set.load(Ordering::SeqCst) {
+///             Poll::Ready(())
+///         } else {
+///             Poll::Pending
+///         }
+///     }
+/// }
+///
+/// impl Flag {
+///     fn set(&self) {
+///         self.set.store(true, Ordering::SeqCst);
+///         self.waker.wake();
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct AtomicWakerCell {
+    cell: OptionalArcCell<Waker>,
+}
+
+impl AtomicWakerCell {
+    /// Create a new AtomicWakerCell with no waker registered, usable in statics.
+    pub const fn new() -> Self {
+        AtomicWakerCell {
+            cell: OptionalArcCell::const_new(),
+        }
+    }
+
+    /// Registers `waker` to be woken by the next call to [`wake`](Self::wake), replacing any
+    /// previously registered waker.
+    ///
+    /// If the registered waker would already wake the same task this doesn't allocate.
+    pub fn register(&self, waker: &Waker) {
+        if let Some(current) = self.cell.get() {
+            if current.will_wake(waker) {
+                return;
+            }
+        }
+        self.cell.set(Some(Arc::new(waker.clone())));
+    }
+
+    /// Wakes the registered waker, if any, and clears the slot.
+    pub fn wake(&self) {
+        if let Some(waker) = self.take() {
+            waker.wake();
+        }
+    }
+
+    /// Takes the registered waker out of the slot without waking it.
+    pub fn take(&self) -> Option<Waker> {
+        self.cell
+            .take_some()
+            .map(|waker| Arc::try_unwrap(waker).unwrap_or_else(|waker| (*waker).clone()))
+    }
+
+    /// Returns whether a waker is currently registered.
+    pub fn is_registered(&self) -> bool {
+        self.cell.is_some()
+    }
+}
+
+impl Debug for AtomicWakerCell {
+    fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
+        fmt.debug_struct("AtomicWakerCell")
+            .field("registered", &self.is_registered())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AtomicWakerCell;
+    use std::sync::{atomic::Ordering, Arc};
+
+    #[test]
+    fn atomic_waker_cell() {
+        use std::sync::atomic::AtomicUsize;
+        use std::task::{Wake, Waker};
+
+        struct CountingWaker(AtomicUsize);
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let cell = Arc::new(AtomicWakerCell::new());
+        cell.wake();
+        assert!(!cell.is_registered());
+
+        cell.register(&waker);
+        cell.register(&waker);
+        assert!(cell.is_registered());
+        let other = cell.clone();
+        std::thread::spawn(move || other.wake()).join().unwrap();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        cell.wake();
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        cell.register(&waker);
+        assert!(cell.take().unwrap().will_wake(&waker));
+        assert!(cell.take().is_none());
+    }
+}